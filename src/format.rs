@@ -0,0 +1,27 @@
+//! File formats for describing 4D objects and their textures, so they can be authored without recompiling the crate.
+
+pub mod mesh;
+pub mod scene;
+pub mod texture;
+
+/// A syntax error, and the line it was found on.
+#[derive(Debug, PartialEq)]
+pub struct Error {
+    pub line: usize,
+    pub message: String,
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parse a file in either format: a mesh if its name ends in `.mesh`, and a scene otherwise.
+pub fn parse(name: &str, source: &str) -> Result<Box<dyn crate::render::Renderable>, Error> {
+    if name.ends_with(".mesh") {
+        Ok(Box::new(mesh::Mesh::read(source)?))
+    } else {
+        scene::parse(source)
+    }
+}
@@ -0,0 +1,356 @@
+//! A 4D analogue of the OBJ format, for exchanging meshes with external tools.
+//!
+//! Each line holds one element, and a `#` starts a comment running to the end of the line.
+//!
+//! - `v x y z w` is a vertex position.
+//! - `vt u v` is a texture coordinate.
+//! - `vn x y z w` is a normal, for lighting. It need not be normalized.
+//! - `f a b c` is a triangle, where each corner is a position index `p`, optionally followed by
+//!   a texture coordinate index as `p/t`, a normal index as `p//n`, or both as `p/t/n`,
+//!   counting from 1 in the order the positions, texture coordinates and normals appear.
+//!   Corners without a texture coordinate use `(0, 0)`, and corners without a normal are not lit.
+//! - `r` starts a new region, a convex cell which hides what lies behind it.
+//! - `h a b c d e` adds to the current region the half-space of points `(x, y, z, w)` with `ax + by + cz + dw + e < 0`.
+//!
+//! Every element must be defined before it is used.
+
+use super::Error;
+use crate::render::{Renderable, Vertex};
+use std::collections::HashMap;
+
+/// A corner of a triangle, as indices into `Mesh::positions`, `Mesh::texcoords` and `Mesh::normals`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Corner {
+    pub position: usize,
+    pub texcoord: Option<usize>,
+    pub normal: Option<usize>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub positions: Vec<nalgebra::Vector4<f32>>,
+    pub texcoords: Vec<[f32; 2]>,
+    pub normals: Vec<nalgebra::Vector4<f32>>,
+    pub triangles: Vec<[Corner; 3]>,
+    pub regions: Vec<Vec<nalgebra::RowVector5<f32>>>,
+}
+
+impl Mesh {
+    /// Capture the triangles and regions of `renderable`, sharing repeated positions, texture coordinates and normals.
+    ///
+    /// Only the texture coordinates are kept, not the textures they refer to or the vertices' colors.
+    pub fn from_renderable(renderable: &(impl ?Sized + Renderable)) -> Self {
+        let mut mesh = Self::default();
+        let mut positions = HashMap::new();
+        let mut texcoords = HashMap::new();
+        let mut normals = HashMap::new();
+
+        let vertices: Vec<Vertex> = renderable.triangles().collect();
+        for triangle in vertices.chunks_exact(3) {
+            let mut corners = [Corner::default(); 3];
+            for (corner, v) in corners.iter_mut().zip(triangle) {
+                // Compare bit patterns, since floats are not `Hash`.
+                let key: Vec<u32> = v.pos.iter().map(|x| x.to_bits()).collect();
+                corner.position = *positions.entry(key).or_insert_with(|| {
+                    mesh.positions.push(v.pos);
+                    mesh.positions.len() - 1
+                });
+                let key = [v.texcoord[0].to_bits(), v.texcoord[1].to_bits()];
+                corner.texcoord = Some(*texcoords.entry(key).or_insert_with(|| {
+                    mesh.texcoords.push(v.texcoord);
+                    mesh.texcoords.len() - 1
+                }));
+                if v.normal != nalgebra::Vector4::zeros() {
+                    let key: Vec<u32> = v.normal.iter().map(|x| x.to_bits()).collect();
+                    corner.normal = Some(*normals.entry(key).or_insert_with(|| {
+                        mesh.normals.push(v.normal);
+                        mesh.normals.len() - 1
+                    }));
+                }
+            }
+            mesh.triangles.push(corners);
+        }
+
+        mesh.regions = renderable.regions().collect();
+        mesh
+    }
+
+    pub fn read(source: &str) -> Result<Self, Error> {
+        let mut mesh = Self::default();
+
+        for (i, line) in source.lines().enumerate() {
+            let error = |message: String| Error {
+                line: i + 1,
+                message,
+            };
+
+            let mut words = line.split('#').next().unwrap_or("").split_whitespace();
+            let keyword = match words.next() {
+                Some(keyword) => keyword,
+                None => continue,
+            };
+            let words: Vec<&str> = words.collect();
+
+            let numbers = |count: usize| -> Result<Vec<f32>, Error> {
+                if words.len() != count {
+                    return Err(error(format!(
+                        "`{}` takes {} numbers, but was given {}",
+                        keyword,
+                        count,
+                        words.len()
+                    )));
+                }
+                words
+                    .iter()
+                    .map(|word| {
+                        word.parse::<f32>()
+                            .ok()
+                            .filter(|x| x.is_finite())
+                            .ok_or_else(|| error(format!("expected a number, found `{}`", word)))
+                    })
+                    .collect()
+            };
+
+            match keyword {
+                "v" => mesh
+                    .positions
+                    .push(nalgebra::Vector4::from_column_slice(&numbers(4)?)),
+                "vt" => {
+                    let t = numbers(2)?;
+                    mesh.texcoords.push([t[0], t[1]]);
+                }
+                "vn" => {
+                    let n = nalgebra::Vector4::from_column_slice(&numbers(4)?);
+                    if n == nalgebra::Vector4::zeros() {
+                        return Err(error("normals must not be zero".into()));
+                    }
+                    mesh.normals.push(n);
+                }
+                "f" => {
+                    if words.len() != 3 {
+                        return Err(error(format!(
+                            "faces must be triangles, but this one has {} corners",
+                            words.len()
+                        )));
+                    }
+                    let mut corners = [Corner::default(); 3];
+                    for (corner, word) in corners.iter_mut().zip(&words) {
+                        *corner = mesh.corner(word).map_err(error)?;
+                    }
+                    mesh.triangles.push(corners);
+                }
+                "r" => {
+                    numbers(0)?;
+                    mesh.regions.push(Vec::new());
+                }
+                "h" => {
+                    let h = nalgebra::RowVector5::from_row_slice(&numbers(5)?);
+                    mesh.regions
+                        .last_mut()
+                        .ok_or_else(|| error("`h` must follow an `r` starting its region".into()))?
+                        .push(h);
+                }
+                _ => return Err(error(format!("unknown element `{}`", keyword))),
+            }
+        }
+
+        Ok(mesh)
+    }
+
+    /// Parse a corner of a face, given as `p`, `p/t`, `p//n` or `p/t/n`.
+    fn corner(&self, word: &str) -> Result<Corner, String> {
+        let index = |text: &str, what: &str, count: usize| {
+            text.parse::<usize>()
+                .ok()
+                .filter(|&i| 1 <= i && i <= count)
+                .map(|i| i - 1)
+                .ok_or_else(|| {
+                    format!(
+                        "`{}` is not the index of one of the {} {} so far",
+                        text, count, what
+                    )
+                })
+        };
+
+        let mut parts = word.splitn(3, '/');
+        let position = index(
+            parts.next().unwrap_or(""),
+            "positions",
+            self.positions.len(),
+        )?;
+        let texcoord = match parts.next() {
+            // The texture coordinate may be left out when a normal follows.
+            Some("") => None,
+            Some(t) => Some(index(t, "texture coordinates", self.texcoords.len())?),
+            None => None,
+        };
+        let normal = match parts.next() {
+            Some(n) => Some(index(n, "normals", self.normals.len())?),
+            None => None,
+        };
+        Ok(Corner {
+            position,
+            texcoord,
+            normal,
+        })
+    }
+
+    pub fn write(&self) -> String {
+        use core::fmt::Write;
+
+        // Writing to a `String` cannot fail.
+        let mut out = String::new();
+        for p in &self.positions {
+            writeln!(out, "v {} {} {} {}", p[0], p[1], p[2], p[3]).unwrap();
+        }
+        for t in &self.texcoords {
+            writeln!(out, "vt {} {}", t[0], t[1]).unwrap();
+        }
+        for n in &self.normals {
+            writeln!(out, "vn {} {} {} {}", n[0], n[1], n[2], n[3]).unwrap();
+        }
+        for triangle in &self.triangles {
+            out.push('f');
+            for corner in triangle {
+                write!(out, " {}", corner.position + 1).unwrap();
+                match (corner.texcoord, corner.normal) {
+                    (Some(t), Some(n)) => write!(out, "/{}/{}", t + 1, n + 1).unwrap(),
+                    (None, Some(n)) => write!(out, "//{}", n + 1).unwrap(),
+                    (Some(t), None) => write!(out, "/{}", t + 1).unwrap(),
+                    (None, None) => {}
+                }
+            }
+            out.push('\n');
+        }
+        for region in &self.regions {
+            out.push_str("r\n");
+            for h in region {
+                writeln!(out, "h {} {} {} {} {}", h[0], h[1], h[2], h[3], h[4]).unwrap();
+            }
+        }
+        out
+    }
+}
+
+impl Renderable for Mesh {
+    fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+        Box::new(self.triangles.iter().flatten().map(move |corner| {
+            Vertex {
+                pos: self.positions[corner.position],
+                texcoord: corner.texcoord.map_or([0., 0.], |t| self.texcoords[t]),
+                texture: None,
+                color: [1.; 4],
+                normal: corner
+                    .normal
+                    .map_or(nalgebra::Vector4::zeros(), |n| self.normals[n].normalize()),
+            }
+        }))
+    }
+
+    fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+        Box::new(self.regions.iter().cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polytope::Polytope;
+    use crate::render::Transform;
+
+    fn error(source: &str) -> Error {
+        Mesh::read(source).unwrap_err()
+    }
+
+    #[test]
+    fn round_trip() {
+        let object = Transform::identity(Polytope::twenty_four_cell())
+            .rotate((0, 3), 0.3)
+            .unwrap()
+            .translate(nalgebra::Vector4::new(0.1, -2., 3.5, 1e-3));
+        let mesh = Mesh::from_renderable(&object);
+        assert_eq!(mesh.regions.len(), 1);
+        // Every vertex of the 24-cell is shared between several triangles.
+        assert_eq!(mesh.positions.len(), 24);
+
+        let read = Mesh::read(&mesh.write()).unwrap();
+        assert_eq!(read, mesh);
+
+        let expected: Vec<_> = object
+            .triangles()
+            .map(|v| (v.pos, v.texcoord, v.normal))
+            .collect();
+        let actual: Vec<_> = read
+            .triangles()
+            .map(|v| (v.pos, v.texcoord, v.normal))
+            .collect();
+        assert_eq!(actual, expected);
+        assert_eq!(
+            read.regions().collect::<Vec<_>>(),
+            object.regions().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reading() {
+        let mesh = Mesh::read(
+            "
+            # A triangle, half of it in shadow.
+            v 0 0 0 -1
+            v 1 0 0 -1
+            v 0 1 0 -1   # trailing comment
+            vt 0.5 0.5
+            vn 0 0 0 2
+            f 1/1 2//1 3/1/1
+
+            r
+            h 0 -1 0 0 0
+            h 0 0 0 1 0.5
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(mesh.positions[2], nalgebra::Vector4::new(0., 1., 0., -1.));
+        let texcoords: Vec<_> = mesh.triangles().map(|v| v.texcoord).collect();
+        assert_eq!(texcoords, vec![[0.5, 0.5], [0., 0.], [0.5, 0.5]]);
+        let normals: Vec<_> = mesh.triangles().map(|v| v.normal).collect();
+        let up = nalgebra::Vector4::new(0., 0., 0., 1.);
+        assert_eq!(normals, vec![nalgebra::Vector4::zeros(), up, up]);
+        assert_eq!(mesh.regions.len(), 1);
+        assert_eq!(
+            mesh.regions[0][1],
+            nalgebra::RowVector5::new(0., 0., 0., 1., 0.5)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            error("v 0 0 0 0\n\nv 1 2 3"),
+            Error {
+                line: 3,
+                message: "`v` takes 4 numbers, but was given 3".into()
+            }
+        );
+        assert_eq!(
+            error("v 0 0 0 0\nf 1 1 2").message,
+            "`2` is not the index of one of the 1 positions so far"
+        );
+        assert_eq!(
+            error("v 0 0 0 0\nf 1/1 1 1").message,
+            "`1` is not the index of one of the 0 texture coordinates so far"
+        );
+        assert_eq!(
+            error("v 0 0 0 0\nf 1 1 1 1").message,
+            "faces must be triangles, but this one has 4 corners"
+        );
+        assert_eq!(
+            error("v 0 0 0 0\nf 1//1 1 1").message,
+            "`1` is not the index of one of the 0 normals so far"
+        );
+        assert_eq!(error("vn 0 0 0 0").message, "normals must not be zero");
+        assert_eq!(error("h 0 0 0 1 0").line, 1);
+        assert_eq!(error("vt 0 x").message, "expected a number, found `x`");
+        assert_eq!(error("o cube").message, "unknown element `o`");
+    }
+}
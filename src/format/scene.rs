@@ -0,0 +1,540 @@
+//! A human-editable text format for scenes.
+//!
+//! A scene is a sequence of statements separated by whitespace.
+//! A `#` starts a comment, which runs to the end of the line.
+//!
+//! Objects:
+//! - `tesseract`, `five_cell`, `sixteen_cell`, `twenty_four_cell`, `hundred_twenty_cell` and `six_hundred_cell`
+//!   are the regular polychora, centered at the origin with circumradius one.
+//! - `hull { x y z w  x y z w ... }` is the convex hull of the given points.
+//! - `{ ... }` is a group of statements.
+//!
+//! Settings, which apply to everything in the group containing them, and must come before the group's objects:
+//! - `translate x y z w`.
+//! - `rotate a b degrees` turns axis `a` towards axis `b`, where the axes are named `x`, `y`, `z` and `w`.
+//! - `scale s` scales uniformly, and `scale x y z w` scales each axis separately.
+//! - `texture name` paints each object with the texture called `name`: `ground`, `bark` or `foliage`.
+//!   Without a name, objects are painted with the default texture, so a bare `texture` undoes an inherited one.
+//!   The name may be followed by `u0 v0 u1 v1`, which maps each object's texture coordinates into that rectangle of the texture,
+//!   or by `u v`, which paints each object with a single texel. Nested groups may choose a different texture.
+//! - `color r g b` tints the group, and `color r g b a` also sets its opacity, which `opacity a` sets alone.
+//!   Nested groups are tinted by both their own color and their parents'.
+//!
+//! Transforms apply in the order they are written, so
+//!
+//! ```text
+//! {
+//!     rotate x w 45
+//!     translate 0 0 0 -5
+//!     tesseract
+//! }
+//! ```
+//!
+//! turns the tesseract about the origin, then moves it in front of the player.
+//! The first axis, `x`, points up.
+
+use super::Error;
+use crate::polytope::Polytope;
+use crate::render::{Renderable, Retexture, Textured, Tinted, Transform};
+use std::rc::Rc;
+
+pub fn parse(source: &str) -> Result<Box<dyn Renderable>, Error> {
+    let mut parser = Parser {
+        tokens: tokenize(source),
+        position: 0,
+        last_line: source.lines().count().max(1),
+    };
+    parser.group(None, Texture::default())
+}
+
+fn polytope(name: &str) -> Option<Polytope> {
+    Some(match name {
+        "tesseract" => Polytope::tesseract(),
+        "five_cell" => Polytope::five_cell(),
+        "sixteen_cell" => Polytope::sixteen_cell(),
+        "twenty_four_cell" => Polytope::twenty_four_cell(),
+        "hundred_twenty_cell" => Polytope::hundred_twenty_cell(),
+        "six_hundred_cell" => Polytope::six_hundred_cell(),
+        _ => return None,
+    })
+}
+
+/// Whether `text` starts a statement, rather than continuing the one before it.
+fn statement(text: &str) -> bool {
+    match text {
+        "{" | "}" | "hull" | "translate" | "rotate" | "scale" | "texture" | "color" | "opacity" => {
+            true
+        }
+        name => polytope(name).is_some(),
+    }
+}
+
+const AXES: &[&str] = &["x", "y", "z", "w"];
+
+#[derive(Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+}
+
+impl Token<'_> {
+    fn error(&self, message: impl Into<String>) -> Error {
+        Error {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+}
+
+/// Split the source into words, with each brace a word of its own.
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        for word in line.split_whitespace() {
+            let mut rest = word;
+            while !rest.is_empty() {
+                let end = match rest.find(&['{', '}'][..]) {
+                    Some(0) => 1,
+                    Some(n) => n,
+                    None => rest.len(),
+                };
+                tokens.push(Token {
+                    text: &rest[..end],
+                    line: i + 1,
+                });
+                rest = &rest[end..];
+            }
+        }
+    }
+    tokens
+}
+
+/// A transform setting, applied when its group is complete.
+enum Step {
+    Translate(nalgebra::Vector4<f32>),
+    Rotate((usize, usize), f32),
+    Scale(nalgebra::Vector4<f32>),
+}
+
+/// A texture setting: the texture to use, if not the default, and the rectangle of it to use, if not all of it.
+#[derive(Clone, Default)]
+struct Texture {
+    image: Option<Rc<crate::render::Texture>>,
+    rectangle: Option<([f32; 2], [f32; 2])>,
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    position: usize,
+    /// The line reported for errors at the end of the input.
+    last_line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.tokens.get(self.position).copied();
+        self.position += 1;
+        token
+    }
+
+    fn peek_number(&self) -> Option<f32> {
+        self.tokens
+            .get(self.position)
+            .and_then(|token| number(token.text))
+    }
+
+    /// Describe the next token for an error message, without consuming it.
+    fn found(&self) -> (usize, String) {
+        match self.tokens.get(self.position) {
+            Some(token) => (token.line, format!("found `{}`", token.text)),
+            None => (self.last_line, "found the end of the file".into()),
+        }
+    }
+
+    fn expected(&self, what: &str, after: &Token) -> Error {
+        let (line, found) = self.found();
+        Error {
+            line,
+            message: format!("expected {} after `{}`, {}", what, after.text, found),
+        }
+    }
+
+    fn number(&mut self, after: &Token) -> Result<f32, Error> {
+        let x = self
+            .peek_number()
+            .ok_or_else(|| self.expected("a number", after))?;
+        self.position += 1;
+        Ok(x)
+    }
+
+    fn vector(&mut self, after: &Token) -> Result<nalgebra::Vector4<f32>, Error> {
+        let mut v = nalgebra::Vector4::zeros();
+        for i in 0..4 {
+            v[i] = self.number(after)?;
+        }
+        Ok(v)
+    }
+
+    fn axis(&mut self, after: &Token) -> Result<usize, Error> {
+        let axis = self
+            .tokens
+            .get(self.position)
+            .and_then(|token| AXES.iter().position(|&a| a == token.text))
+            .ok_or_else(|| self.expected("one of the axes `x`, `y`, `z` and `w`", after))?;
+        self.position += 1;
+        Ok(axis)
+    }
+
+    /// Parse statements up to the brace closing `open`, or to the end of the input if `open` is `None`.
+    fn group(
+        &mut self,
+        open: Option<&Token>,
+        mut texture: Texture,
+    ) -> Result<Box<dyn Renderable>, Error> {
+        let mut steps = Vec::new();
+        let mut objects: Vec<Box<dyn Renderable>> = Vec::new();
+        let mut color = [1.; 4];
+
+        loop {
+            let token = match (self.next(), open) {
+                (Some(token), _) => token,
+                (None, None) => break,
+                (None, Some(open)) => return Err(open.error("this `{` is never closed")),
+            };
+
+            match token.text {
+                "}" if open.is_some() => break,
+                "}" => return Err(token.error("this `}` does not close anything")),
+                "translate" | "rotate" | "scale" | "texture" | "color" | "opacity"
+                    if !objects.is_empty() =>
+                {
+                    return Err(token.error(format!(
+                        "`{}` must come before the objects in its group",
+                        token.text
+                    )))
+                }
+                "translate" => steps.push(Step::Translate(self.vector(&token)?)),
+                "rotate" => {
+                    let plane = (self.axis(&token)?, self.axis(&token)?);
+                    if plane.0 == plane.1 {
+                        return Err(token.error("cannot rotate an axis towards itself"));
+                    }
+                    let angle = self.number(&token)?.to_radians();
+                    steps.push(Step::Rotate(plane, angle));
+                }
+                "scale" => {
+                    let first = self.number(&token)?;
+                    let factors = match self.peek_number() {
+                        Some(_) => {
+                            self.position -= 1;
+                            self.vector(&token)?
+                        }
+                        None => nalgebra::Vector4::repeat(first),
+                    };
+                    if factors.iter().any(|&x| x == 0.) {
+                        return Err(token.error("scale factors must be nonzero"));
+                    }
+                    steps.push(Step::Scale(factors));
+                }
+                "texture" => {
+                    let image = match self.tokens.get(self.position) {
+                        Some(name) if number(name.text).is_none() => {
+                            match crate::render::Texture::named(name.text) {
+                                Some(image) => {
+                                    self.position += 1;
+                                    Some(image)
+                                }
+                                // A bare `texture` goes back to the default texture.
+                                None if statement(name.text) => None,
+                                None => {
+                                    return Err(
+                                        self.expected("a number or the name of a texture", &token)
+                                    )
+                                }
+                            }
+                        }
+                        _ => None,
+                    };
+                    let rectangle = match self.peek_number() {
+                        None => None,
+                        Some(_) => {
+                            let min = [self.number(&token)?, self.number(&token)?];
+                            let max = match self.peek_number() {
+                                Some(_) => [self.number(&token)?, self.number(&token)?],
+                                None => min,
+                            };
+                            Some((min, max))
+                        }
+                    };
+                    texture = Texture { image, rectangle };
+                }
+                "color" => {
+                    for c in &mut color[..3] {
+                        *c = self.number(&token)?;
+                    }
+                    if self.peek_number().is_some() {
+                        color[3] = self.number(&token)?;
+                    }
+                    if color.iter().any(|&c| c < 0.) {
+                        return Err(token.error("colors must not be negative"));
+                    }
+                }
+                "opacity" => {
+                    color[3] = self.number(&token)?;
+                    if color[3] < 0. {
+                        return Err(token.error("opacity must not be negative"));
+                    }
+                }
+                "{" => objects.push(self.group(Some(&token), texture.clone())?),
+                "hull" => objects.push(textured(self.hull(&token)?, texture.clone())),
+                name => match polytope(name) {
+                    Some(polytope) => objects.push(textured(polytope, texture.clone())),
+                    None => return Err(token.error(format!("unknown statement `{}`", name))),
+                },
+            }
+        }
+
+        let objects: Box<dyn Renderable> = if color == [1.; 4] {
+            Box::new(objects)
+        } else {
+            Box::new(Tinted {
+                inner: objects,
+                color,
+            })
+        };
+        let transform = steps
+            .into_iter()
+            .try_fold(Transform::identity(objects), |t, step| match step {
+                Step::Translate(v) => Some(t.translate(v)),
+                Step::Rotate(plane, angle) => t.rotate(plane, angle),
+                Step::Scale(factors) => t.scale(factors),
+            })
+            .expect("steps are checked as they are parsed");
+        Ok(Box::new(transform))
+    }
+
+    fn hull(&mut self, keyword: &Token) -> Result<Polytope, Error> {
+        match self.next() {
+            Some(token) if token.text == "{" => {}
+            _ => {
+                self.position -= 1;
+                return Err(self.expected("`{`", keyword));
+            }
+        }
+
+        let mut coordinates = Vec::new();
+        while let Some(x) = self.peek_number() {
+            coordinates.push(x);
+            self.position += 1;
+        }
+        match self.next() {
+            Some(token) if token.text == "}" => {}
+            _ => {
+                self.position -= 1;
+                return Err(self.expected("a number or `}`", keyword));
+            }
+        }
+
+        if coordinates.len() % 4 != 0 {
+            return Err(keyword.error(format!(
+                "the number of coordinates, {}, is not a multiple of four",
+                coordinates.len()
+            )));
+        }
+        let points: Vec<_> = coordinates
+            .chunks(4)
+            .map(nalgebra::Vector4::from_column_slice)
+            .collect();
+        Polytope::hull(&points)
+            .ok_or_else(|| keyword.error("the points of a hull must not all lie in one hyperplane"))
+    }
+}
+
+fn number(text: &str) -> Option<f32> {
+    text.parse::<f32>().ok().filter(|x| x.is_finite())
+}
+
+fn textured(object: Polytope, texture: Texture) -> Box<dyn Renderable> {
+    let object: Box<dyn Renderable> = match texture.rectangle {
+        Some((min, max)) => Box::new(Retexture {
+            inner: object,
+            min,
+            max,
+        }),
+        None => Box::new(object),
+    };
+    match texture.image {
+        Some(image) => Box::new(Textured {
+            inner: object,
+            texture: image,
+        }),
+        None => object,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> Error {
+        match parse(source) {
+            Ok(_) => panic!("parsed {:?}", source),
+            Err(err) => err,
+        }
+    }
+
+    fn assert_same_regions(a: &dyn Renderable, b: &dyn Renderable) {
+        let a: Vec<_> = a.regions().flatten().collect();
+        let b: Vec<_> = b.regions().flatten().collect();
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(&b) {
+            assert!((a - b).norm() < 1e-5, "{} is not close to {}", a, b);
+        }
+    }
+
+    #[test]
+    fn example_scene() {
+        let scene = parse(include_str!("../../resources/scenes/example.scene")).unwrap();
+        assert_eq!(scene.regions().count(), 5);
+        assert!(scene.triangles().count() > 0);
+    }
+
+    #[test]
+    fn transforms_apply_in_order() {
+        let scene = parse(
+            "
+            rotate x w 90
+            { scale 2 1 1 1  translate 0 0 0 -5  tesseract }
+            ",
+        )
+        .unwrap();
+
+        let expected = Transform::nonuniform_scaling(
+            Polytope::tesseract(),
+            nalgebra::Vector4::new(2., 1., 1., 1.),
+        )
+        .unwrap()
+        .translate(nalgebra::Vector4::new(0., 0., 0., -5.))
+        .rotate((0, 3), core::f32::consts::FRAC_PI_2)
+        .unwrap();
+        assert_same_regions(&*scene, &expected);
+    }
+
+    #[test]
+    fn hulls() {
+        let scene = parse("hull{0 0 0 0  1 0 0 0  0 1 0 0  0 0 1 0  0 0 0 1}").unwrap();
+        let expected = Polytope::hull(&[
+            nalgebra::Vector4::zeros(),
+            nalgebra::Vector4::x(),
+            nalgebra::Vector4::y(),
+            nalgebra::Vector4::z(),
+            nalgebra::Vector4::w(),
+        ])
+        .unwrap();
+        assert_same_regions(&*scene, &expected);
+    }
+
+    #[test]
+    fn textures_are_inherited_until_overridden() {
+        let scene = parse(
+            "
+            texture 0.5 0.25
+            five_cell
+            { texture 0 0 0.5 0.5  five_cell }
+            ",
+        )
+        .unwrap();
+        let texcoords: Vec<_> = scene.triangles().map(|v| v.texcoord).collect();
+        let (outer, inner) = texcoords.split_at(texcoords.len() / 2);
+        assert!(outer.iter().all(|&t| t == [0.5, 0.25]));
+        assert!(inner
+            .iter()
+            .all(|t| t.iter().all(|&x| (0. ..=0.5).contains(&x))));
+        assert!(inner.iter().any(|t| t[0] > 0.));
+    }
+
+    #[test]
+    fn textures_can_be_named() {
+        let scene = parse(
+            "
+            texture bark
+            five_cell
+            { texture foliage 0 1  five_cell }
+            { texture 1 1  five_cell }
+            ",
+        )
+        .unwrap();
+        let vertices: Vec<_> = scene.triangles().collect();
+        let (bark, rest) = vertices.split_at(vertices.len() / 3);
+        let (foliage, default) = rest.split_at(rest.len() / 2);
+        let named = |v: &crate::render::Vertex, name| match &v.texture {
+            Some(texture) => Rc::ptr_eq(texture, &crate::render::Texture::named(name).unwrap()),
+            None => false,
+        };
+        assert!(bark.iter().all(|v| named(v, "bark")));
+        assert!(bark.iter().any(|v| v.texcoord != [0., 0.]));
+        assert!(foliage
+            .iter()
+            .all(|v| named(v, "foliage") && v.texcoord == [0., 1.]));
+        assert!(default
+            .iter()
+            .all(|v| v.texture.is_none() && v.texcoord == [1., 1.]));
+        // A bare `texture` undoes its group's inherited texture and rectangle.
+        let scene = parse("texture bark 0 0  { texture  five_cell }").unwrap();
+        assert!(scene.triangles().all(|v| v.texture.is_none()));
+        assert!(scene.triangles().any(|v| v.texcoord != [0., 0.]));
+        assert_eq!(
+            error("texture wood").message,
+            "expected a number or the name of a texture after `texture`, found `wood`"
+        );
+    }
+
+    #[test]
+    fn nested_colors_multiply() {
+        let scene = parse(
+            "
+            color 0.5 1 1
+            five_cell
+            { color 1 1 0.5 0.5  opacity 0.25  five_cell }
+            ",
+        )
+        .unwrap();
+        let colors: Vec<_> = scene.triangles().map(|v| v.color).collect();
+        let (outer, inner) = colors.split_at(colors.len() / 2);
+        assert!(outer.iter().all(|&c| c == [0.5, 1., 1., 1.]));
+        assert!(inner.iter().all(|&c| c == [0.5, 1., 0.5, 0.25]));
+        assert_eq!(error("color 1 -1 1").message, "colors must not be negative");
+    }
+
+    #[test]
+    fn errors_report_their_lines() {
+        assert_eq!(
+            error("tesseract\n\n  cube # not a polytope"),
+            Error {
+                line: 3,
+                message: "unknown statement `cube`".into()
+            }
+        );
+        assert_eq!(error("{\n tesseract\n").line, 1);
+        assert_eq!(error("tesseract\n}").line, 2);
+        assert_eq!(
+            error("translate 1 2\n3").message,
+            "expected a number after `translate`, found the end of the file"
+        );
+        assert_eq!(
+            error("rotate x q 30").message,
+            "expected one of the axes `x`, `y`, `z` and `w` after `rotate`, found `q`"
+        );
+        assert_eq!(
+            error("tesseract scale 2").message,
+            "`scale` must come before the objects in its group"
+        );
+        assert_eq!(error("scale 1 0 1 1").line, 1);
+        assert_eq!(error("hull { 0 0 0 0  1 1 1 }").line, 1);
+        assert_eq!(
+            error("hull { 0 0 0 0  1 0 0 0  0 1 0 0  0 0 1 0  1 1 1 0 }").message,
+            "the points of a hull must not all lie in one hyperplane"
+        );
+    }
+}
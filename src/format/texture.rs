@@ -0,0 +1,216 @@
+//! Readers for textures, so they can be kept as editable images rather than raw RGBA data.
+//!
+//! In ASCII art, each character is one texel, whose color is looked up in a palette.
+//! Every line is one row of texels, from `v = 0` at the top, and all of them must be the same length.
+
+use super::Error;
+use crate::render::Texture;
+
+pub fn read_ascii_art(source: &str, palette: &[(char, [u8; 4])]) -> Result<Texture, Error> {
+    let mut width = None;
+    let mut pixels = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let error = |message: String| Error {
+            line: i + 1,
+            message,
+        };
+
+        let length = line.chars().count();
+        let width = *width.get_or_insert(length);
+        if length != width {
+            return Err(error(format!(
+                "this row is {} texels wide, but the first was {}",
+                length, width
+            )));
+        }
+
+        for c in line.chars() {
+            let &(_, color) = palette
+                .iter()
+                .find(|&&(p, _)| p == c)
+                .ok_or_else(|| error(format!("`{}` is not in the palette", c)))?;
+            pixels.extend_from_slice(&color);
+        }
+    }
+
+    match width {
+        Some(width) if width > 0 => Ok(Texture::new(width, pixels.len() / (4 * width), pixels)),
+        _ => Err(Error {
+            line: 1,
+            message: "the texture is empty".into(),
+        }),
+    }
+}
+
+/// Read a PNG image of any color type and bit depth, with its top row at `v = 0`.
+pub fn read_png(bytes: &[u8]) -> Result<Texture, String> {
+    let mut decoder = png::Decoder::new(bytes);
+    // Expand palettes, transparent colors and small bit depths to 8 bits per channel, and reduce 16 bits to 8.
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let (info, mut reader) = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut data).map_err(|e| e.to_string())?;
+
+    let pixels = match reader.output_color_type() {
+        (png::ColorType::RGBA, png::BitDepth::Eight) => data,
+        (png::ColorType::RGB, png::BitDepth::Eight) => data
+            .chunks_exact(3)
+            .flat_map(|c| vec![c[0], c[1], c[2], 255])
+            .collect(),
+        (png::ColorType::GrayscaleAlpha, png::BitDepth::Eight) => data
+            .chunks_exact(2)
+            .flat_map(|c| vec![c[0], c[0], c[0], c[1]])
+            .collect(),
+        (png::ColorType::Grayscale, png::BitDepth::Eight) => {
+            data.iter().flat_map(|&c| vec![c, c, c, 255]).collect()
+        }
+        (color_type, bit_depth) => {
+            return Err(format!(
+                "could not convert {:?} pixels of {} bits to RGBA",
+                color_type, bit_depth as u8
+            ))
+        }
+    };
+
+    let (width, height) = (info.width as usize, info.height as usize);
+    if width == 0 || height == 0 {
+        return Err("the image is empty".into());
+    }
+    if width > Texture::MAX_WIDTH {
+        return Err(format!(
+            "the image is {} pixels wide, but textures may be at most {}",
+            width,
+            Texture::MAX_WIDTH
+        ));
+    }
+    Ok(Texture::new(width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PALETTE: &[(char, [u8; 4])] = &[('#', [1, 2, 3, 4]), ('.', [5, 6, 7, 8])];
+
+    /// Encode a PNG image, with the given chunks, such as a palette, between its header and its data.
+    fn write_png(
+        width: u32,
+        height: u32,
+        (color, depth): (png::ColorType, png::BitDepth),
+        chunks: &[(&[u8; 4], &[u8])],
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(color);
+        encoder.set_depth(depth);
+        let mut writer = encoder.write_header().unwrap();
+        for &(&name, chunk) in chunks {
+            writer.write_chunk(name, chunk).unwrap();
+        }
+        writer.write_image_data(data).unwrap();
+        drop(writer);
+        out
+    }
+
+    #[test]
+    fn ascii_art() {
+        let texture = read_ascii_art("#..\n.#.\r\n", PALETTE).unwrap();
+        assert_eq!((texture.width(), texture.height()), (3, 2));
+        assert_eq!(texture.texel(0, 0), [1, 2, 3, 4]);
+        assert_eq!(texture.texel(2, 0), [5, 6, 7, 8]);
+        assert_eq!(texture.texel(1, 1), [1, 2, 3, 4]);
+
+        assert_eq!(
+            read_ascii_art("#.\n#.\n#", PALETTE).unwrap_err(),
+            Error {
+                line: 3,
+                message: "this row is 1 texels wide, but the first was 2".into()
+            }
+        );
+        assert_eq!(
+            read_ascii_art("##\n#x", PALETTE).unwrap_err().message,
+            "`x` is not in the palette"
+        );
+        assert_eq!(
+            read_ascii_art("", PALETTE).unwrap_err().message,
+            "the texture is empty"
+        );
+    }
+
+    #[test]
+    fn png_color_types() {
+        let eight = |color| (color, png::BitDepth::Eight);
+        let rgba = write_png(
+            2,
+            1,
+            eight(png::ColorType::RGBA),
+            &[],
+            &[1, 2, 3, 4, 5, 6, 7, 8],
+        );
+        let rgb = write_png(2, 1, eight(png::ColorType::RGB), &[], &[1, 2, 3, 5, 6, 7]);
+        let gray = write_png(1, 2, eight(png::ColorType::Grayscale), &[], &[9, 10]);
+
+        let texture = read_png(&rgba).unwrap();
+        assert_eq!((texture.width(), texture.height()), (2, 1));
+        assert_eq!(texture.texel(1, 0), [5, 6, 7, 8]);
+        assert_eq!(read_png(&rgb).unwrap().texel(1, 0), [5, 6, 7, 255]);
+        assert_eq!(read_png(&gray).unwrap().texel(0, 1), [10, 10, 10, 255]);
+
+        assert!(read_png(b"not a png").is_err());
+    }
+
+    #[test]
+    fn png_bit_depths() {
+        let rgb = write_png(
+            1,
+            1,
+            (png::ColorType::RGB, png::BitDepth::Sixteen),
+            &[],
+            &[0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc],
+        );
+        assert_eq!(read_png(&rgb).unwrap().texel(0, 0), [0x12, 0x56, 0x9a, 255]);
+
+        // Eight pixels of one bit each, from the most significant.
+        let gray = write_png(
+            8,
+            1,
+            (png::ColorType::Grayscale, png::BitDepth::One),
+            &[],
+            &[0b1000_0000],
+        );
+        let texture = read_png(&gray).unwrap();
+        assert_eq!(texture.texel(0, 0), [255, 255, 255, 255]);
+        assert_eq!(texture.texel(1, 0), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn png_palettes_and_transparency() {
+        let indexed = (png::ColorType::Indexed, png::BitDepth::Eight);
+        let palette: (&[u8; 4], &[u8]) = (b"PLTE", &[1, 2, 3, 4, 5, 6]);
+
+        let opaque = write_png(2, 1, indexed, &[palette], &[1, 0]);
+        let texture = read_png(&opaque).unwrap();
+        assert_eq!(texture.texel(0, 0), [4, 5, 6, 255]);
+        assert_eq!(texture.texel(1, 0), [1, 2, 3, 255]);
+
+        // Palette entries without an alpha in `tRNS` are opaque.
+        let translucent = write_png(2, 1, indexed, &[palette, (b"tRNS", &[64])], &[1, 0]);
+        let texture = read_png(&translucent).unwrap();
+        assert_eq!(texture.texel(0, 0), [4, 5, 6, 255]);
+        assert_eq!(texture.texel(1, 0), [1, 2, 3, 64]);
+
+        // In images without a palette, `tRNS` gives one transparent color, with 16 bits per channel.
+        let keyed = write_png(
+            2,
+            1,
+            (png::ColorType::RGB, png::BitDepth::Eight),
+            &[(b"tRNS", &[0, 5, 0, 6, 0, 7])],
+            &[1, 2, 3, 5, 6, 7],
+        );
+        let texture = read_png(&keyed).unwrap();
+        assert_eq!(texture.texel(0, 0), [1, 2, 3, 255]);
+        assert_eq!(texture.texel(1, 0), [5, 6, 7, 0]);
+    }
+}
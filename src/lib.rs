@@ -2,8 +2,10 @@
 
 mod utils;
 
+mod format;
 mod fps;
 mod model;
+mod polytope;
 mod render;
 
 use wasm_bindgen::prelude::*;
@@ -11,8 +13,52 @@ use wasm_bindgen::JsCast;
 
 use std::sync::mpsc;
 
+/// Lets the page's scripts change settings while the game is running.
 #[wasm_bindgen]
-pub fn run() -> Result<(), JsValue> {
+pub struct Handle {
+    sender: mpsc::Sender<model::Msg>,
+}
+
+#[wasm_bindgen]
+impl Handle {
+    /// Set the four-camera's field of view, in degrees.
+    pub fn set_fov(&self, degrees: f32) {
+        self.sender
+            .send(model::Msg::SetFov(degrees.to_radians()))
+            .unwrap_throw();
+    }
+
+    /// Switch between perspective and orthographic projection.
+    pub fn set_orthographic(&self, orthographic: bool) {
+        let projection = if orthographic {
+            model::Projection::Orthographic
+        } else {
+            model::Projection::Perspective
+        };
+        self.sender
+            .send(model::Msg::SetProjection(projection))
+            .unwrap_throw();
+    }
+
+    /// Light the world from the direction `(x, y, z, w)`, where `x` is up, or turn lighting off with `(0, 0, 0, 0)`.
+    pub fn set_light(&self, x: f32, y: f32, z: f32, w: f32) {
+        self.sender
+            .send(model::Msg::SetLight(nalgebra::Vector4::new(x, y, z, w)))
+            .unwrap_throw();
+    }
+
+    /// Resolve to everything in the scene, written in the mesh format, when the next frame is drawn.
+    pub fn export_mesh(&self) -> js_sys::Promise {
+        js_sys::Promise::new(&mut |resolve, _reject| {
+            self.sender
+                .send(model::Msg::ExportMesh(resolve))
+                .unwrap_throw();
+        })
+    }
+}
+
+#[wasm_bindgen]
+pub fn run() -> Result<Handle, JsValue> {
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
 
     let window = web_sys::window().ok_or("no global `window` exists")?;
@@ -46,5 +92,5 @@ pub fn run() -> Result<(), JsValue> {
     *g.borrow_mut() = Some(Closure::wrap(Box::new(closure)));
     window.request_animation_frame(g.borrow().as_ref().unwrap_throw().as_ref().unchecked_ref())?;
 
-    Ok(())
+    Ok(Handle { sender })
 }
@@ -1,28 +1,46 @@
+mod collision;
+mod double_quaternion;
+mod forest;
 mod four_camera;
+mod random;
+mod simulation;
+mod terrain;
+mod three_camera;
 mod world;
 
+#[cfg(test)]
+mod golden;
+
 use crate::{fps, render};
-use core::f32::consts::FRAC_PI_2;
-use four_camera::FourCamera;
+pub use four_camera::Projection;
+use four_camera::FOV_RANGE;
+use simulation::{Input, Simulation};
 use world::World;
 
-use std::collections::HashSet;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
 pub struct Model {
-    keys: HashSet<String>,
     fps: Option<fps::FrameCounter>,
     render: Box<render::RenderFunction>,
+    scene: render::Scene,
+    /// The objects in the scene making up the world, removed when another scene is loaded.
+    objects: Vec<render::ObjectId>,
 
     window: web_sys::Window,
     document: web_sys::Document,
+    canvas: web_sys::HtmlCanvasElement,
+    sender: std::sync::mpsc::Sender<Msg>,
     info_box: web_sys::HtmlParagraphElement,
+    /// A message shown below the frame rate, such as why a scene failed to load.
+    status: Option<String>,
     slice_slider: web_sys::HtmlInputElement,
+    fov_slider: web_sys::HtmlInputElement,
     vr_status: std::rc::Rc<std::cell::RefCell<VrStatus>>,
+    /// The unit vector pointing towards the light, or zero if lighting is off.
+    light: nalgebra::Vector4<f32>,
 
-    four_camera: FourCamera,
-    world: world::World,
+    simulation: Simulation,
 }
 
 #[derive(Clone)]
@@ -42,6 +60,24 @@ pub enum Msg {
     KeyDown(String),
     KeyUp(String),
     SliceSliderSlid,
+    FovSliderSlid,
+    /// The window changed size, or moved to a screen with a different pixel density.
+    Resize,
+    DragOver,
+    Drop(Option<web_sys::File>),
+    /// The name and contents of a scene file, or why it could not be read.
+    LoadScene {
+        name: String,
+        source: Result<String, String>,
+    },
+
+    /// Set the four-camera's field of view, in radians.
+    SetFov(f32),
+    SetProjection(Projection),
+    /// Set the direction towards the light, which need not be normalized, or turn lighting off with zero.
+    SetLight(nalgebra::Vector4<f32>),
+    /// Call the function with the scene, written in the mesh format.
+    ExportMesh(js_sys::Function),
 
     GotVRDisplays(js_sys::Array),
     DisplayPresenting(web_sys::VrDisplay),
@@ -60,9 +96,10 @@ impl Model {
         let canvas = document
             .create_element("canvas")?
             .dyn_into::<web_sys::HtmlCanvasElement>()?;
-        canvas.set_attribute("width", "1600")?;
-        canvas.set_attribute("height", "800")?;
+        // The canvas spans the page, and its drawing buffer follows its size on screen.
+        canvas.set_attribute("style", "display: block; width: 100%; aspect-ratio: 2 / 1")?;
         body.append_child(&canvas)?;
+        resize(&window, &canvas);
 
         let info_box = document
             .create_element("p")?
@@ -79,8 +116,61 @@ impl Model {
         slice_slider.set_value("10");
         body.append_child(&slice_slider)?;
 
-        let world = World::default();
-        let render = render::make_fn(&canvas, world.to_renderable())?;
+        // The four-camera's field of view, in degrees.
+        let fov_slider = document
+            .create_element("input")?
+            .dyn_into::<web_sys::HtmlInputElement>()?;
+
+        fov_slider.set_type("range");
+        fov_slider.set_min(&FOV_RANGE.0.to_degrees().to_string());
+        fov_slider.set_max(&FOV_RANGE.1.to_degrees().to_string());
+        fov_slider.set_step("any");
+        body.append_child(&fov_slider)?;
+
+        let params = web_sys::UrlSearchParams::new_with_str(&window.location().search()?)?;
+
+        // A forest may be generated from a seed in the page's URL, as in `index.html?forest=42`.
+        let world = World {
+            forest: params
+                .get("forest")
+                .and_then(|seed| seed.parse().ok())
+                .map(|seed| forest::Forest {
+                    seed,
+                    ..forest::Forest::default()
+                }),
+            // Likewise for hilly terrain, as in `index.html?terrain=7`.
+            terrain: params
+                .get("terrain")
+                .and_then(|seed| seed.parse().ok())
+                .map(|seed| terrain::Terrain {
+                    seed,
+                    extent: 24.,
+                    resolution: 12,
+                    wavelength: 8.,
+                    ..terrain::Terrain::default()
+                }),
+        };
+        let render = render::make_fn(&canvas)?;
+        let mut scene = render::Scene::new();
+        let objects = world
+            .objects()
+            .into_iter()
+            .map(|(object, matrix)| {
+                let id = scene.add(object);
+                scene
+                    .set_transform(id, matrix)
+                    .ok_or("objects are placed by invertible matrices")?;
+                Ok(id)
+            })
+            .collect::<Result<_, JsValue>>()?;
+        let simulation = Simulation::new(&scene);
+
+        // A scene may be loaded from the page's URL, as in `index.html?scene=level.scene`.
+        let mut status = None;
+        if let Some(url) = params.get("scene") {
+            status = Some(format!("Loading {}...", url));
+            fetch_scene(&window, url, sender.clone());
+        }
 
         let vr_status = std::rc::Rc::new(std::cell::RefCell::new(VrStatus::Searching));
 
@@ -134,7 +224,24 @@ impl Model {
             let evt = evt.dyn_into::<web_sys::KeyboardEvent>().unwrap_throw();
             Msg::KeyUp(evt.key())
         })?;
+        // Files dropped onto the canvas are loaded as scenes.
+        crate::utils::event_listener(&sender, &canvas, "dragover", |evt| {
+            evt.prevent_default();
+            Msg::DragOver
+        })?;
+        crate::utils::event_listener(&sender, &canvas, "drop", |evt| {
+            evt.prevent_default();
+            let evt = evt.dyn_into::<web_sys::DragEvent>().unwrap_throw();
+            Msg::Drop(
+                evt.data_transfer()
+                    .and_then(|data| data.files())
+                    .and_then(|files| files.get(0)),
+            )
+        })?;
+        crate::utils::event_listener(&sender, &window, "resize", |_| Msg::Resize)?;
+        watch_pixel_ratio(&window, sender.clone())?;
         crate::utils::event_listener(&sender, &slice_slider, "input", |_| Msg::SliceSliderSlid)?;
+        crate::utils::event_listener(&sender, &fov_slider, "input", |_| Msg::FovSliderSlid)?;
 
         let navigator: web_sys::Navigator = window.navigator();
 
@@ -156,50 +263,68 @@ impl Model {
         }
 
         Ok(Self {
-            keys: HashSet::new(),
             fps: None,
             render,
+            scene,
+            objects,
 
             window,
             document,
+            canvas,
+            sender,
             info_box,
+            status,
             slice_slider,
+            fov_slider,
             vr_status,
+            light: default_light(),
 
-            four_camera: FourCamera::default(),
-            world,
+            simulation,
         })
     }
 
     pub fn update(&mut self, msg: Msg) -> Result<(), JsValue> {
         match msg {
             Msg::Click => {}
-            Msg::KeyDown(k) => {
-                self.keys.insert(k.to_lowercase());
-            }
-            Msg::KeyUp(k) => {
-                self.keys.remove(&k.to_lowercase());
-            }
-            Msg::MouseMove([x, y]) => {
+            Msg::KeyDown(k) => self.simulation.input(Input::KeyDown(k)),
+            Msg::KeyUp(k) => self.simulation.input(Input::KeyUp(k)),
+            Msg::MouseMove(movement) => {
                 if self.document.pointer_lock_element().is_some() {
-                    self.four_camera.orientation.horizontal *= nalgebra::UnitQuaternion::new(
-                        nalgebra::Vector3::new(0., -x as f32 * 3e-3, 0.),
-                    );
-                    self.four_camera.orientation.vertical += y as f32 * 3e-3;
-                    self.four_camera.orientation.vertical =
-                        self.four_camera.orientation.vertical.min(FRAC_PI_2);
-                    self.four_camera.orientation.vertical =
-                        self.four_camera.orientation.vertical.max(-FRAC_PI_2);
+                    self.simulation.input(Input::Look(movement));
                 }
             }
             Msg::MouseWheel(z) => {
                 if self.document.pointer_lock_element().is_some() {
-                    self.four_camera.orientation.horizontal *= nalgebra::UnitQuaternion::new(
-                        nalgebra::Vector3::new(-z as f32 * 1e-2, 0., 0.),
-                    );
+                    self.simulation.input(Input::Scroll(z));
                 }
             }
             Msg::SliceSliderSlid => {}
+            Msg::FovSliderSlid => self
+                .simulation
+                .four_camera
+                .set_fov((self.fov_slider.value_as_number() as f32).to_radians()),
+            Msg::SetFov(fov) => self.simulation.four_camera.set_fov(fov),
+            Msg::SetProjection(projection) => self.simulation.four_camera.projection = projection,
+            Msg::SetLight(light) => {
+                self.light = if light == nalgebra::Vector4::zeros() {
+                    light
+                } else {
+                    light.normalize()
+                }
+            }
+            Msg::ExportMesh(callback) => {
+                let mesh = crate::format::mesh::Mesh::from_renderable(&self.scene);
+                callback.call1(&JsValue::NULL, &mesh.write().into())?;
+            }
+            Msg::Resize => resize(&self.window, &self.canvas),
+            Msg::DragOver => {}
+            Msg::Drop(file) => {
+                if let Some(file) = file {
+                    self.status = Some(format!("Loading {}...", file.name()));
+                    read_scene(file, &self.sender)?;
+                }
+            }
+            Msg::LoadScene { name, source } => self.load_scene(&name, source),
             Msg::GotVRDisplays(vr_displays) => {
                 if vr_displays.length() == 0 {
                     *self.vr_status.borrow_mut() = VrStatus::NotFound;
@@ -219,37 +344,45 @@ impl Model {
         if let Some(fps) = &mut self.fps {
             dt = fps.frame(time);
 
-            self.info_box.set_inner_text(&format!("{}", fps));
-
-            self.move_player(dt);
-
-            (self.render)(render::Uniforms {
-                four_camera: self.four_camera.projection_matrix(),
-                four_camera_pos: self.four_camera.position,
-                three_screen_size: [1., 1., 0.1 * self.slice_slider.value_as_number() as f32],
-                three_cameras: if let VrStatus::Presenting(display) =
-                    self.vr_status.borrow().clone()
-                {
-                    let frame_data = web_sys::VrFrameData::new()?;
-                    display.get_frame_data(&frame_data);
-
-                    [
-                        (nalgebra::Matrix4::from_iterator(frame_data.left_projection_matrix()?)
-                            * nalgebra::Matrix4::from_iterator(frame_data.left_view_matrix()?)),
-                        (nalgebra::Matrix4::from_iterator(frame_data.right_projection_matrix()?)
-                            * nalgebra::Matrix4::from_iterator(frame_data.right_view_matrix()?)),
-                    ]
-                } else {
-                    [
-                        nalgebra::Matrix4::new(
-                            1., 0., 0., 0., 0., 1., 0., 0., 0., 0., -1., 2.98, 0., 0., -1., 3.,
-                        ),
-                        nalgebra::Matrix4::new(
-                            1., 0., 0., 0., 0., 1., 0., 0., 0., 0., -1., 2.98, 0., 0., -1., 3.,
-                        ),
-                    ]
+            self.info_box.set_inner_text(&match &self.status {
+                Some(status) => format!("{}\n{}", fps, status),
+                None => format!("{}", fps),
+            });
+
+            self.simulation.step(dt);
+
+            let four_camera = &self.simulation.four_camera;
+            self.fov_slider
+                .set_value_as_number(f64::from(four_camera.fov().to_degrees()));
+
+            (self.render)(
+                &mut self.scene,
+                render::Uniforms {
+                    four_camera: four_camera.projection_matrix(),
+                    four_camera_pos: four_camera.position,
+                    four_camera_forward: four_camera.forward(),
+                    orthographic: four_camera.projection == Projection::Orthographic,
+                    three_screen_size: [1., 1., 0.1 * self.slice_slider.value_as_number() as f32],
+                    light: self.light,
+                    three_cameras: if let VrStatus::Presenting(display) =
+                        self.vr_status.borrow().clone()
+                    {
+                        let frame_data = web_sys::VrFrameData::new()?;
+                        display.get_frame_data(&frame_data);
+
+                        [
+                            (nalgebra::Matrix4::from_iterator(
+                                frame_data.left_projection_matrix()?,
+                            ) * nalgebra::Matrix4::from_iterator(frame_data.left_view_matrix()?)),
+                            (nalgebra::Matrix4::from_iterator(
+                                frame_data.right_projection_matrix()?,
+                            ) * nalgebra::Matrix4::from_iterator(frame_data.right_view_matrix()?)),
+                        ]
+                    } else {
+                        self.simulation.three_camera.matrices()
+                    },
                 },
-            })?;
+            )?;
 
             if let VrStatus::Presenting(display) = self.vr_status.borrow().clone() {
                 display.submit_frame();
@@ -261,6 +394,28 @@ impl Model {
         Ok(())
     }
 
+    /// Replace the world with the scene in `source`, or report why that is impossible.
+    ///
+    /// The renderer is kept, and only re-uploads what changed in the scene.
+    fn load_scene(&mut self, name: &str, source: Result<String, String>) {
+        let world = match source
+            .and_then(|source| crate::format::parse(name, &source).map_err(|err| err.to_string()))
+        {
+            Ok(world) => world,
+            Err(err) => {
+                self.status = Some(format!("Could not load {}: {}", name, err));
+                return;
+            }
+        };
+
+        for id in self.objects.drain(..) {
+            self.scene.remove(id);
+        }
+        self.objects.push(self.scene.add(world));
+        self.simulation.set_world(&self.scene);
+        self.status = Some(format!("Loaded {}", name));
+    }
+
     pub fn request_animation_frame(&self, callback: &js_sys::Function) -> Result<i32, JsValue> {
         if let VrStatus::Presenting(display) = self.vr_status.borrow().clone() {
             display.request_animation_frame(callback)
@@ -270,32 +425,94 @@ impl Model {
     }
 }
 
-impl Model {
-    fn move_player(&mut self, dt: f64) {
-        let m = self.four_camera.orientation.horizontal_to_mat() * dt as f32;
-        if self.keys.contains(" ") {
-            self.four_camera.position += m * nalgebra::Vector4::new(1., 0., 0., 0.);
-        }
-        if self.keys.contains("shift") {
-            self.four_camera.position += m * nalgebra::Vector4::new(-1., 0., 0., 0.);
-        }
-        if self.keys.contains("w") {
-            self.four_camera.position += m * nalgebra::Vector4::new(0., 0., 0., -1.);
-        }
-        if self.keys.contains("s") {
-            self.four_camera.position += m * nalgebra::Vector4::new(0., 0., 0., 1.);
-        }
-        if self.keys.contains("d") {
-            self.four_camera.position += m * nalgebra::Vector4::new(0., 1., 0., 0.);
-        }
-        if self.keys.contains("a") {
-            self.four_camera.position += m * nalgebra::Vector4::new(0., -1., 0., 0.);
+/// Light from above, tilted so that faces turned along each horizontal axis are shaded differently.
+fn default_light() -> nalgebra::Vector4<f32> {
+    nalgebra::Vector4::new(1., 0.3, 0.2, 0.5).normalize()
+}
+
+/// Give the canvas one pixel of drawing buffer for each physical pixel it covers on screen.
+fn resize(window: &web_sys::Window, canvas: &web_sys::HtmlCanvasElement) {
+    let scale = window.device_pixel_ratio();
+    let physical = |css: i32| (f64::from(css) * scale).round().max(1.) as u32;
+    canvas.set_width(physical(canvas.client_width()));
+    canvas.set_height(physical(canvas.client_height()));
+}
+
+/// Send `Msg::Resize` when the pixel ratio changes, as when the window moves to a screen of a different density.
+/// Browsers fire no `resize` event then, so this watches a media query matching the current ratio instead.
+fn watch_pixel_ratio(
+    window: &web_sys::Window,
+    sender: std::sync::mpsc::Sender<Msg>,
+) -> Result<(), JsValue> {
+    let query = format!("(resolution: {}dppx)", window.device_pixel_ratio());
+    let list = match window.match_media(&query)? {
+        Some(list) => list,
+        None => return Ok(()),
+    };
+
+    // The query stops matching once the ratio changes, so replace it with one for the new ratio.
+    let window_ = window.clone();
+    let list_ = list.clone();
+    let callback = Closure::once_into_js(move || {
+        list_.set_onchange(None);
+        sender.send(Msg::Resize).unwrap_throw();
+        if let Err(err) = watch_pixel_ratio(&window_, sender) {
+            web_sys::console::error_1(&err);
         }
-        if self.keys.contains("q") {
-            self.four_camera.position += m * nalgebra::Vector4::new(0., 0., 1., 0.);
+    });
+    list.set_onchange(Some(callback.unchecked_ref()));
+    Ok(())
+}
+
+fn fetch_scene(window: &web_sys::Window, url: String, sender: std::sync::mpsc::Sender<Msg>) {
+    let response = window.fetch_with_str(&url);
+    let send = move |source| {
+        sender
+            .send(Msg::LoadScene {
+                name: url.clone(),
+                source,
+            })
+            .unwrap_throw()
+    };
+
+    crate::utils::on_settled(&response, move |response| {
+        let response = match response.and_then(|r| r.dyn_into::<web_sys::Response>()) {
+            Ok(response) => response,
+            Err(err) => return send(Err(crate::utils::describe(&err))),
+        };
+        if !response.ok() {
+            return send(Err(format!(
+                "{} {}",
+                response.status(),
+                response.status_text()
+            )));
         }
-        if self.keys.contains("e") {
-            self.four_camera.position += m * nalgebra::Vector4::new(0., 0., -1., 0.);
+        match response.text() {
+            Ok(text) => crate::utils::on_settled(&text, move |text| {
+                send(
+                    text.map(|text| text.as_string().unwrap_or_default())
+                        .map_err(|err| crate::utils::describe(&err)),
+                )
+            }),
+            Err(err) => send(Err(crate::utils::describe(&err))),
         }
-    }
+    });
+}
+
+fn read_scene(file: web_sys::File, sender: &std::sync::mpsc::Sender<Msg>) -> Result<(), JsValue> {
+    let reader = web_sys::FileReader::new()?;
+    let reader_ = reader.clone();
+    let name = file.name();
+    let sender = sender.clone();
+    // Unlike a listener, this is freed once it has been called.
+    let callback = Closure::once_into_js(move || {
+        let source = reader_
+            .result()
+            .ok()
+            .and_then(|result| result.as_string())
+            .ok_or_else(|| "the file could not be read".into());
+        sender.send(Msg::LoadScene { name, source }).unwrap_throw();
+    });
+    reader.set_onloadend(Some(callback.unchecked_ref()));
+    reader.read_as_text(&file)
 }
@@ -0,0 +1,201 @@
+//! Collision detection between the player and the regions of the world.
+
+type Region = Vec<nalgebra::RowVector5<f32>>;
+
+/// How close the player may come to a region's boundary.
+pub const RADIUS: f32 = 0.2;
+
+/// How far outside a boundary the player is kept after touching it, so rounding errors cannot carry it inside.
+const SKIN: f32 = 1e-3;
+
+/// The regions the player collides with, each grown by `RADIUS`.
+pub struct Solids {
+    regions: Vec<Region>,
+}
+
+impl Solids {
+    pub fn new(regions: impl Iterator<Item = Region>) -> Self {
+        let regions = regions
+            .filter_map(|region| {
+                region
+                    .into_iter()
+                    .map(|h| {
+                        let norm = h.fixed_columns::<nalgebra::U4>(0).norm();
+                        // A half-space with no normal is either everything or nothing.
+                        if norm == 0. {
+                            return if h[4] < 0. { Ok(None) } else { Err(()) };
+                        }
+                        let mut h = h / norm;
+                        h[4] -= RADIUS;
+                        Ok(Some(h))
+                    })
+                    .filter_map(Result::transpose)
+                    .collect::<Result<Region, ()>>()
+                    .ok()
+            })
+            .filter(|region| !region.is_empty())
+            .collect();
+
+        Self { regions }
+    }
+
+    /// Move from `from` by `displacement`, stopping at the boundaries of the regions and sliding along them.
+    pub fn slide(
+        &self,
+        from: nalgebra::Vector4<f32>,
+        displacement: nalgebra::Vector4<f32>,
+    ) -> nalgebra::Vector4<f32> {
+        let mut position = from;
+        let mut displacement = displacement;
+
+        // Each contact removes a direction of movement, so there can be at most four.
+        for _ in 0..4 {
+            match self.first_hit(&position, &displacement) {
+                None => return position + displacement,
+                Some((t, normal)) => {
+                    position += displacement * t + normal * SKIN;
+                    displacement *= 1. - t;
+                    displacement -= normal * normal.dot(&displacement).min(0.);
+                }
+            }
+        }
+        position
+    }
+
+    /// Whether the player is standing on a boundary no steeper than 45 degrees, relative to `up`.
+    pub fn supported(
+        &self,
+        position: &nalgebra::Vector4<f32>,
+        up: &nalgebra::Vector4<f32>,
+    ) -> bool {
+        // Touching boundaries are `SKIN` away, so look a little further than that.
+        matches!(
+            self.first_hit(position, &(-up * 2. * SKIN)),
+            Some((_, normal)) if normal.dot(up) > core::f32::consts::FRAC_1_SQRT_2
+        )
+    }
+
+    /// The first time in `[0, 1]` at which `position + t * displacement` enters a region,
+    /// and the outward normal of the boundary it enters through.
+    fn first_hit(
+        &self,
+        position: &nalgebra::Vector4<f32>,
+        displacement: &nalgebra::Vector4<f32>,
+    ) -> Option<(f32, nalgebra::Vector4<f32>)> {
+        let mut first: Option<(f32, nalgebra::Vector4<f32>)> = None;
+
+        for region in &self.regions {
+            let mut enter = (f32::NEG_INFINITY, nalgebra::Vector4::zeros());
+            let mut exit = f32::INFINITY;
+            for h in region {
+                let normal = h.fixed_columns::<nalgebra::U4>(0).transpose();
+                let a = normal.dot(position) + h[4];
+                let b = normal.dot(displacement);
+                if b < 0. {
+                    if -a / b > enter.0 {
+                        enter = (-a / b, normal);
+                    }
+                } else if b > 0. {
+                    exit = exit.min(-a / b);
+                } else if a >= 0. {
+                    exit = f32::NEG_INFINITY;
+                }
+            }
+
+            // A player who somehow got inside a region may leave it freely.
+            let (t, normal) = enter;
+            if (0. ..=1.).contains(&t) && t < exit && t < first.map_or(f32::INFINITY, |(s, _)| s) {
+                first = Some((t, normal));
+            }
+        }
+
+        first
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The unit tesseract `[-1, 1]^4`.
+    fn tesseract() -> Solids {
+        let mut region = Vec::new();
+        for i in 0..4 {
+            for &sign in &[1., -1.] {
+                let mut h = nalgebra::RowVector5::zeros();
+                h[i] = sign;
+                h[4] = -1.;
+                region.push(h);
+            }
+        }
+        Solids::new(std::iter::once(region))
+    }
+
+    fn assert_close(a: nalgebra::Vector4<f32>, b: nalgebra::Vector4<f32>) {
+        assert!((a - b).norm() < 1e-2, "{} is not close to {}", a, b);
+    }
+
+    #[test]
+    fn free_movement() {
+        let p = tesseract().slide(
+            nalgebra::Vector4::new(3., 0., 0., 0.),
+            nalgebra::Vector4::new(0., 1., 2., 0.),
+        );
+        assert_close(p, nalgebra::Vector4::new(3., 1., 2., 0.));
+    }
+
+    #[test]
+    fn stops_at_a_wall() {
+        let p = tesseract().slide(
+            nalgebra::Vector4::new(3., 0., 0., 0.),
+            nalgebra::Vector4::new(-4., 0., 0., 0.),
+        );
+        assert_close(p, nalgebra::Vector4::new(1. + RADIUS, 0., 0., 0.));
+    }
+
+    #[test]
+    fn slides_along_walls() {
+        let p = tesseract().slide(
+            nalgebra::Vector4::new(3., 0., 0., 0.),
+            nalgebra::Vector4::new(-4., 0.5, 0., -1.),
+        );
+        assert_close(p, nalgebra::Vector4::new(1. + RADIUS, 0.5, 0., -1.));
+    }
+
+    #[test]
+    fn misses_past_corners() {
+        let p = tesseract().slide(
+            nalgebra::Vector4::new(3., 3., 0., 0.),
+            nalgebra::Vector4::new(-6., 0., 0., 0.),
+        );
+        assert_close(p, nalgebra::Vector4::new(-3., 3., 0., 0.));
+    }
+
+    #[test]
+    fn standing_on_top() {
+        let solids = tesseract();
+        let up = nalgebra::Vector4::new(1., 0., 0., 0.);
+        let p = solids.slide(nalgebra::Vector4::new(2., 0.5, 0., 0.), -up * 2.);
+        assert!(solids.supported(&p, &up));
+        assert!(!solids.supported(&(p + up * 0.1), &up));
+
+        // Walls are too steep to stand on.
+        let p = solids.slide(
+            nalgebra::Vector4::new(0., 2., 0., 0.),
+            nalgebra::Vector4::new(0., -2., 0., 0.),
+        );
+        assert!(!solids.supported(&p, &up));
+    }
+
+    #[test]
+    fn degenerate_half_spaces() {
+        let solids = Solids::new(std::iter::once(vec![nalgebra::RowVector5::new(
+            0., 0., 0., 0., -1.,
+        )]));
+        let p = solids.slide(
+            nalgebra::Vector4::zeros(),
+            nalgebra::Vector4::new(1., 0., 0., 0.),
+        );
+        assert_close(p, nalgebra::Vector4::new(1., 0., 0., 0.));
+    }
+}
@@ -0,0 +1,160 @@
+//! Rotations of 4D space, as pairs of unit quaternions.
+//!
+//! Identifying the point `(x, y, z, w)` with the quaternion `x + yi + zj + wk`,
+//! every rotation has the form `p -> l * p * r` for unit quaternions `l` and `r`.
+
+use core::ops::Mul;
+
+type Quaternion = nalgebra::Quaternion<f32>;
+type UnitQuaternion = nalgebra::UnitQuaternion<f32>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DoubleQuaternion {
+    left: UnitQuaternion,
+    right: UnitQuaternion,
+}
+
+/// The unit vector along the `i`th coordinate axis.
+fn axis(i: usize) -> nalgebra::Vector4<f32> {
+    let mut v = nalgebra::Vector4::zeros();
+    v[i] = 1.;
+    v
+}
+
+/// The quaternion corresponding to the `i`th coordinate axis.
+fn basis(i: usize) -> Quaternion {
+    to_quaternion(&axis(i))
+}
+
+fn to_quaternion(v: &nalgebra::Vector4<f32>) -> Quaternion {
+    Quaternion::new(v[0], v[1], v[2], v[3])
+}
+
+fn from_quaternion(q: &Quaternion) -> nalgebra::Vector4<f32> {
+    nalgebra::Vector4::new(q.w, q.i, q.j, q.k)
+}
+
+/// `exp(u * angle)`, for a unit imaginary quaternion `u`.
+fn exp(u: Quaternion, angle: f32) -> UnitQuaternion {
+    let (s, c) = angle.sin_cos();
+    UnitQuaternion::new_normalize(Quaternion::new(c, 0., 0., 0.) + u * s)
+}
+
+impl DoubleQuaternion {
+    /// The rotation by `angle` in the plane of coordinate axes `a` and `b`, turning axis `a` towards axis `b`.
+    pub fn plane(a: usize, b: usize, angle: f32) -> Self {
+        assert!(a != b && a < 4 && b < 4, "invalid rotation plane");
+
+        if a == 0 {
+            // Multiplying by `exp(u * angle / 2)` on both sides rotates the plane of `1` and `u`,
+            // while the turns in the orthogonal plane cancel out.
+            let q = exp(basis(b), angle / 2.);
+            Self { left: q, right: q }
+        } else if b == 0 {
+            Self::plane(b, a, -angle)
+        } else {
+            // Conjugating by `exp(u * angle / 2)` rotates the imaginary axes about `u`.
+            let q = exp(basis(a) * basis(b), angle / 2.);
+            Self {
+                left: q,
+                right: q.inverse(),
+            }
+        }
+    }
+
+    /// The rotation acting on the last three axes `(y, z, w)` as `q` does on 3D space, and fixing the first.
+    pub fn from_rotation3(q: UnitQuaternion) -> Self {
+        Self {
+            left: q,
+            right: q.inverse(),
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            left: self.left.inverse(),
+            right: self.right.inverse(),
+        }
+    }
+
+    pub fn transform_vector(&self, v: &nalgebra::Vector4<f32>) -> nalgebra::Vector4<f32> {
+        from_quaternion(&(self.left.into_inner() * to_quaternion(v) * self.right.into_inner()))
+    }
+
+    pub fn to_matrix(self) -> nalgebra::Matrix4<f32> {
+        let columns: Vec<_> = (0..4).map(|i| self.transform_vector(&axis(i))).collect();
+        nalgebra::Matrix4::from_columns(&columns)
+    }
+}
+
+impl Mul for DoubleQuaternion {
+    type Output = Self;
+
+    /// The rotation applying `rhs` first, then `self`.
+    fn mul(self, rhs: Self) -> Self {
+        // Renormalize, so that rounding errors do not build up over many small rotations.
+        Self {
+            left: UnitQuaternion::new_normalize(self.left.into_inner() * rhs.left.into_inner()),
+            right: UnitQuaternion::new_normalize(rhs.right.into_inner() * self.right.into_inner()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: nalgebra::Matrix4<f32>, b: nalgebra::Matrix4<f32>) {
+        assert!((a - b).norm() < 1e-5, "{} is not close to {}", a, b);
+    }
+
+    fn plane_matrix(a: usize, b: usize, angle: f32) -> nalgebra::Matrix4<f32> {
+        let (s, c) = angle.sin_cos();
+        let mut m = nalgebra::Matrix4::identity();
+        m[(a, a)] = c;
+        m[(b, a)] = s;
+        m[(a, b)] = -s;
+        m[(b, b)] = c;
+        m
+    }
+
+    #[test]
+    fn plane_rotations() {
+        for a in 0..4 {
+            for b in 0..4 {
+                if a != b {
+                    assert_close(
+                        DoubleQuaternion::plane(a, b, 0.7).to_matrix(),
+                        plane_matrix(a, b, 0.7),
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn composition_and_inverse() {
+        let p = DoubleQuaternion::plane(0, 2, 0.4);
+        let q = DoubleQuaternion::plane(1, 3, -1.1);
+        let r = DoubleQuaternion::plane(3, 2, 2.5);
+
+        assert_close(
+            (p * q * r).to_matrix(),
+            plane_matrix(0, 2, 0.4) * plane_matrix(1, 3, -1.1) * plane_matrix(3, 2, 2.5),
+        );
+        assert_close(
+            ((p * q * r) * (p * q * r).inverse()).to_matrix(),
+            nalgebra::Matrix4::identity(),
+        );
+    }
+
+    #[test]
+    fn from_rotation3_fixes_the_first_axis() {
+        let q = nalgebra::UnitQuaternion::new(nalgebra::Vector3::new(0.3, -0.5, 0.8));
+        let mut expected = nalgebra::Matrix4::identity();
+        expected
+            .fixed_slice_mut::<nalgebra::U3, nalgebra::U3>(1, 1)
+            .copy_from(q.to_rotation_matrix().matrix());
+        assert_close(DoubleQuaternion::from_rotation3(q).to_matrix(), expected);
+    }
+}
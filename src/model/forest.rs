@@ -0,0 +1,167 @@
+//! Reproducible forests, scattered across the ground from a seed.
+
+use super::random::Random;
+
+/// How many random positions to try for each tree before giving up on finding room for more.
+const ATTEMPTS_PER_TREE: usize = 30;
+
+/// Settings for scattering trees across the ground.
+#[derive(Clone, Debug)]
+pub struct Forest {
+    pub seed: u64,
+    /// How many trees to place. Fewer are placed if there is not enough room.
+    pub count: usize,
+    /// Trees are placed in the cube `[-extent, extent]^3` of horizontal space.
+    pub extent: f32,
+    /// The least distance between two trees, or between a tree and the player's starting point.
+    pub spacing: f32,
+    /// The range of sizes, relative to the default tree.
+    pub scale: (f32, f32),
+}
+
+impl Default for Forest {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            count: 30,
+            extent: 20.,
+            spacing: 4.,
+            scale: (0.6, 1.2),
+        }
+    }
+}
+
+/// Where a tree stands, and how it is turned and scaled.
+#[derive(Debug, PartialEq)]
+pub struct Placement {
+    /// The position along the horizontal axes `(y, z, w)`.
+    pub position: nalgebra::Vector3<f32>,
+    /// A rotation of the horizontal axes `(y, z, w)`, which keeps the tree upright,
+    /// so it is a 3D rotation rather than a general 4D one.
+    ///
+    /// It is drawn from all such rotations, rather than from `icosahedral_group` as first planned:
+    /// a tree is symmetric under that group, so turning it by one of those rotations would change nothing.
+    pub rotation: nalgebra::UnitQuaternion<f32>,
+    pub scale: f32,
+}
+
+impl Forest {
+    /// Place trees by dart throwing, rejecting any position too close to an earlier tree.
+    pub fn placements(&self) -> Vec<Placement> {
+        let mut random = Random::new(self.seed);
+        let mut placements: Vec<Placement> = Vec::new();
+
+        for _ in 0..self.count * ATTEMPTS_PER_TREE {
+            if placements.len() == self.count {
+                break;
+            }
+
+            let position = nalgebra::Vector3::new(
+                random.range(-self.extent, self.extent),
+                random.range(-self.extent, self.extent),
+                random.range(-self.extent, self.extent),
+            );
+            let rotation = random.rotation();
+            let scale = random.range(self.scale.0, self.scale.1);
+
+            // The player starts at the origin, which is kept clear.
+            if position.norm() < self.spacing
+                || placements
+                    .iter()
+                    .any(|p| (p.position - position).norm() < self.spacing)
+            {
+                continue;
+            }
+
+            placements.push(Placement {
+                position,
+                rotation,
+                scale,
+            });
+        }
+
+        placements
+    }
+
+    /// The matrices placing a tree at each of the placements,
+    /// standing on the ground at the height given by `height` for its horizontal position.
+    pub fn transforms(
+        &self,
+        height: impl Fn(&nalgebra::Vector3<f32>) -> f32,
+    ) -> Vec<nalgebra::Matrix5<f32>> {
+        self.placements()
+            .into_iter()
+            .map(|p| {
+                let mut m = nalgebra::Matrix5::identity() * p.scale;
+                m.fixed_slice_mut::<nalgebra::U3, nalgebra::U3>(1, 1)
+                    .copy_from(&(p.rotation.to_rotation_matrix().matrix() * p.scale));
+                m.fixed_slice_mut::<nalgebra::U4, nalgebra::U1>(0, 4)
+                    .copy_from(&p.position.insert_row(0, height(&p.position)));
+                m[(4, 4)] = 1.;
+                m
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::world::icosahedral_group;
+
+    #[test]
+    fn forests_are_reproducible() {
+        let forest = Forest::default();
+        assert_eq!(forest.placements(), forest.placements());
+
+        let other = Forest {
+            seed: 1,
+            ..Forest::default()
+        };
+        assert_ne!(forest.placements(), other.placements());
+    }
+
+    #[test]
+    fn trees_are_spread_out_within_the_bounds() {
+        let forest = Forest {
+            seed: 12,
+            count: 50,
+            ..Forest::default()
+        };
+        let placements = forest.placements();
+        assert_eq!(placements.len(), 50);
+
+        for (i, a) in placements.iter().enumerate() {
+            assert!(a.position.amax() <= forest.extent);
+            assert!(a.position.norm() >= forest.spacing);
+            assert!(forest.scale.0 <= a.scale && a.scale < forest.scale.1);
+            for b in &placements[i + 1..] {
+                assert!((a.position - b.position).norm() >= forest.spacing);
+            }
+        }
+    }
+
+    #[test]
+    fn trees_are_turned_differently() {
+        // Trees turned by rotations differing only by a symmetry would look the same.
+        let symmetries: Vec<_> = icosahedral_group().collect();
+        let placements = Forest::default().placements();
+        for (i, a) in placements.iter().enumerate() {
+            for b in &placements[i + 1..] {
+                let difference = a.rotation.inverse() * b.rotation;
+                assert!(symmetries.iter().all(|s| s.angle_to(&difference) > 1e-3));
+            }
+        }
+    }
+
+    #[test]
+    fn crowded_forests_stop_early() {
+        let forest = Forest {
+            count: 1000,
+            extent: 5.,
+            ..Forest::default()
+        };
+        let placements = forest.placements();
+        assert!(!placements.is_empty() && placements.len() < 1000);
+    }
+}
@@ -1,6 +1,22 @@
+use super::double_quaternion::DoubleQuaternion;
+
+/// The range of allowed fields of view, in radians.
+pub const FOV_RANGE: (f32, f32) = (0.1, 3.);
+
+/// In orthographic projection, objects at this distance appear the same size as they would in perspective.
+const FOCUS_DISTANCE: f32 = 5.;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Projection {
+    Perspective,
+    /// Parallel projection along the direction of view, so that size does not depend on distance.
+    Orthographic,
+}
+
 pub struct FourCamera {
     pub position: nalgebra::Vector4<f32>,
     pub orientation: Orientation,
+    pub projection: Projection,
     fov: f32,
 }
 
@@ -9,21 +25,47 @@ impl Default for FourCamera {
         Self {
             position: nalgebra::Vector4::zeros(),
             orientation: Orientation::default(),
+            projection: Projection::Perspective,
             fov: 1.57,
         }
     }
 }
 
 impl FourCamera {
+    pub fn fov(&self) -> f32 {
+        self.fov
+    }
+
+    /// Set the field of view, in radians, clamping it to `FOV_RANGE`.
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov.clamp(FOV_RANGE.0, FOV_RANGE.1);
+    }
+
+    /// The direction the camera is looking in.
+    pub fn forward(&self) -> nalgebra::Vector4<f32> {
+        self.orientation.to_rotation().to_matrix() * nalgebra::Vector4::new(0., 0., 0., -1.)
+    }
+
     #[rustfmt::skip]
     pub fn projection_matrix(&self) -> nalgebra::Matrix4x5<f32> {
-        let x = (self.fov / 2.).tan();
-        let projection = nalgebra::Matrix4x5::new(
-            x, 0., 0., 0., 0.,
-            0., x, 0., 0., 0.,
-            0., 0., x, 0., 0.,
-            0., 0., 0., -1., 0.,
-        );
+        let x = 1. / (self.fov / 2.).tan();
+        let projection = match self.projection {
+            Projection::Perspective => nalgebra::Matrix4x5::new(
+                x, 0., 0., 0., 0.,
+                0., x, 0., 0., 0.,
+                0., 0., x, 0., 0.,
+                0., 0., 0., -1., 0.,
+            ),
+            Projection::Orthographic => {
+                let x = x / FOCUS_DISTANCE;
+                nalgebra::Matrix4x5::new(
+                    x, 0., 0., 0., 0.,
+                    0., x, 0., 0., 0.,
+                    0., 0., x, 0., 0.,
+                    0., 0., 0., 0., 1.,
+                )
+            }
+        };
 
         let rotation = self.orientation.to_homogeneous_inverse();
 
@@ -33,12 +75,26 @@ impl FourCamera {
     }
 }
 
-pub struct Orientation {
+/// How the camera is oriented.
+pub enum Orientation {
+    /// Kept upright, so that the first axis always points up on screen unless looking straight up or down.
+    Upright(Upright),
+    /// Any rotation at all, for free flight.
+    Free(DoubleQuaternion),
+}
+
+pub struct Upright {
     pub vertical: f32,
     pub horizontal: nalgebra::UnitQuaternion<f32>,
 }
 
 impl Default for Orientation {
+    fn default() -> Self {
+        Orientation::Upright(Upright::default())
+    }
+}
+
+impl Default for Upright {
     fn default() -> Self {
         Self {
             vertical: 0.,
@@ -48,6 +104,40 @@ impl Default for Orientation {
 }
 
 impl Orientation {
+    /// The rotation taking the camera's axes to the world's.
+    pub fn to_rotation(&self) -> DoubleQuaternion {
+        match self {
+            Orientation::Upright(upright) => upright.to_rotation(),
+            Orientation::Free(rotation) => *rotation,
+        }
+    }
+
+    /// The rotation applied to the player's movement, which, when upright, ignores looking up and down.
+    pub fn movement_mat(&self) -> nalgebra::Matrix4<f32> {
+        match self {
+            Orientation::Upright(upright) => upright.horizontal_to_mat(),
+            Orientation::Free(rotation) => rotation.to_matrix(),
+        }
+    }
+
+    /// Switch to free flight, keeping the current view.
+    pub fn free(&mut self) {
+        *self = Orientation::Free(self.to_rotation());
+    }
+
+    /// Switch to upright mode, at the upright orientation closest to the current view.
+    pub fn snap_upright(&mut self) {
+        if let Orientation::Free(rotation) = self {
+            *self = Orientation::Upright(Upright::nearest(&rotation.to_matrix()));
+        }
+    }
+
+    fn to_homogeneous_inverse(&self) -> nalgebra::Matrix5<f32> {
+        self.to_rotation().inverse().to_matrix().to_homogeneous()
+    }
+}
+
+impl Upright {
     pub fn horizontal_to_mat(&self) -> nalgebra::Matrix4<f32> {
         let mut mat = nalgebra::Matrix4::identity();
         mat.fixed_slice_mut::<nalgebra::U3, nalgebra::U3>(1, 1)
@@ -55,19 +145,49 @@ impl Orientation {
         mat
     }
 
-    fn to_homogeneous_inverse(&self) -> nalgebra::Matrix5<f32> {
-        let mut horizontal = nalgebra::Matrix5::identity();
-        horizontal
-            .fixed_slice_mut::<nalgebra::U3, nalgebra::U3>(1, 1)
-            .copy_from(&self.horizontal.conjugate().to_rotation_matrix().matrix());
-
-        let mut vertical = nalgebra::Matrix5::identity();
-        let (s, c) = self.vertical.sin_cos();
-        vertical[(0, 0)] = c;
-        vertical[(0, 3)] = -s;
-        vertical[(3, 0)] = s;
-        vertical[(3, 3)] = c;
-
-        vertical * horizontal
+    pub fn to_rotation(&self) -> DoubleQuaternion {
+        // Looking down by `vertical` turns the camera's first axis towards its `-w` axis.
+        DoubleQuaternion::from_rotation3(self.horizontal)
+            * DoubleQuaternion::plane(0, 3, -self.vertical)
+    }
+
+    /// The upright orientation looking in the same direction as the rotation `m`,
+    /// and otherwise as close to it as possible.
+    fn nearest(m: &nalgebra::Matrix4<f32>) -> Self {
+        let horizontal_part = |v: nalgebra::Vector4<f32>| nalgebra::Vector3::new(v[1], v[2], v[3]);
+
+        // The camera looks along its `-w` axis.
+        let forward = m * nalgebra::Vector4::new(0., 0., 0., -1.);
+        let vertical = (-forward[0]).clamp(-1., 1.).asin();
+
+        // The columns of the horizontal rotation: first the direction of view...
+        let mut w = -horizontal_part(forward);
+        if w.norm() < 1e-3 {
+            // Looking straight up or down, the camera's up axis points horizontally forward or back.
+            w = -vertical.signum() * horizontal_part(m * nalgebra::Vector4::new(1., 0., 0., 0.));
+        }
+        let w = w.normalize();
+        // ...then the camera's second axis (or, if that is degenerate, its third), made horizontal and perpendicular to the first.
+        let perpendicular = |v: nalgebra::Vector4<f32>| {
+            let v = horizontal_part(m * v);
+            v - w * w.dot(&v)
+        };
+        let mut y = perpendicular(nalgebra::Vector4::new(0., 1., 0., 0.));
+        if y.norm() < 1e-3 {
+            y = w.cross(&perpendicular(nalgebra::Vector4::new(0., 0., 1., 0.)));
+        }
+        let y = y.normalize();
+        let z = w.cross(&y);
+
+        let horizontal = nalgebra::UnitQuaternion::from_rotation_matrix(
+            &nalgebra::Rotation3::from_matrix_unchecked(nalgebra::Matrix3::from_columns(&[
+                y, z, w,
+            ])),
+        );
+
+        Self {
+            vertical,
+            horizontal,
+        }
     }
 }
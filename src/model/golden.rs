@@ -0,0 +1,269 @@
+//! Renders canned views of the world with the software renderer,
+//! and compares them against the reference images in `tests/golden`.
+//!
+//! After an intended change to the output, regenerate the reference images by running
+//! `UPDATE_GOLDEN=1 cargo test golden`, and check the new images before committing them.
+
+use super::forest::Forest;
+use super::four_camera::{FourCamera, Orientation, Projection, Upright};
+use super::terrain::Terrain;
+use super::three_camera::ThreeCamera;
+use super::world::World;
+use crate::render::{software, Uniforms};
+use core::f32::consts::{FRAC_PI_2, PI};
+use std::path::PathBuf;
+
+/// The side length of each eye's view, in pixels.
+const SIZE: usize = 128;
+/// How far a channel may differ from the reference, out of 255, before the pixel counts as changed.
+const CHANNEL_TOLERANCE: u8 = 3;
+/// The fraction of pixels that may change, to allow for rounding differences along triangle edges.
+const PIXEL_TOLERANCE: f32 = 0.01;
+
+fn camera(
+    position: [f32; 4],
+    vertical: f32,
+    horizontal: nalgebra::UnitQuaternion<f32>,
+) -> FourCamera {
+    let mut camera = FourCamera::default();
+    camera.position = position.into();
+    camera.orientation = Orientation::Upright(Upright {
+        vertical,
+        horizontal,
+    });
+    camera
+}
+
+/// Rotate the horizontal directions `(y, z, w)` about the given axis.
+fn turn(axis: nalgebra::Unit<nalgebra::Vector3<f32>>, angle: f32) -> nalgebra::UnitQuaternion<f32> {
+    nalgebra::UnitQuaternion::from_axis_angle(&axis, angle)
+}
+
+fn check(name: &str, camera: FourCamera) {
+    check_world(name, &World::default(), camera);
+}
+
+fn check_world(name: &str, world: &World, camera: FourCamera) {
+    let uniforms = Uniforms {
+        four_camera: camera.projection_matrix(),
+        four_camera_pos: camera.position,
+        four_camera_forward: camera.forward(),
+        orthographic: camera.projection == Projection::Orthographic,
+        three_cameras: ThreeCamera::default().matrices(),
+        three_screen_size: [1., 1., 1.],
+        light: super::default_light(),
+    };
+    let image = software::render(&world.to_renderable(), &uniforms, SIZE);
+    let actual: Vec<u8> = image
+        .pixels()
+        .iter()
+        .flat_map(|p| p[..3].iter().map(|c| (c * 255.).round() as u8))
+        .collect();
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        write_png(&path, image.width, image.height, &actual);
+        return;
+    }
+
+    let expected = read_png(&path, image.width, image.height).unwrap_or_else(|err| {
+        panic!(
+            "could not read {}: {}; run with UPDATE_GOLDEN=1 to create it",
+            path.display(),
+            err
+        )
+    });
+
+    let changed = actual
+        .chunks(3)
+        .zip(expected.chunks(3))
+        .filter(|(a, b)| {
+            a.iter()
+                .zip(b.iter())
+                .any(|(a, b)| a.max(b) - a.min(b) > CHANNEL_TOLERANCE)
+        })
+        .count();
+
+    let allowed = (PIXEL_TOLERANCE * (image.width * image.height) as f32) as usize;
+    if changed > allowed {
+        let actual_path = std::env::temp_dir().join(format!("{}.png", name));
+        write_png(&actual_path, image.width, image.height, &actual);
+        panic!(
+            "{} of {} pixels differ from {}; the new image is at {}",
+            changed,
+            image.width * image.height,
+            path.display(),
+            actual_path.display()
+        );
+    }
+}
+
+fn write_png(path: &std::path::Path, width: usize, height: usize, data: &[u8]) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let file = std::fs::File::create(path).unwrap();
+    let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+    encoder.set_color(png::ColorType::RGB);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(data)
+        .unwrap();
+}
+
+fn read_png(path: &std::path::Path, width: usize, height: usize) -> Result<Vec<u8>, String> {
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let (info, mut reader) = png::Decoder::new(file)
+        .read_info()
+        .map_err(|e| e.to_string())?;
+    if (info.width as usize, info.height as usize) != (width, height)
+        || info.color_type != png::ColorType::RGB
+        || info.bit_depth != png::BitDepth::Eight
+    {
+        return Err(format!(
+            "expected a {}x{} 8-bit RGB image, found a {}x{} {:?} {:?} one",
+            width, height, info.width, info.height, info.bit_depth, info.color_type
+        ));
+    }
+    let mut data = vec![0; info.buffer_size()];
+    reader.next_frame(&mut data).map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+#[test]
+fn default_view() {
+    check("default", FourCamera::default());
+}
+
+#[test]
+fn from_afar() {
+    check(
+        "from_afar",
+        camera([0., 0., 0., 3.], 0., nalgebra::UnitQuaternion::identity()),
+    );
+}
+
+#[test]
+fn looking_along_positive_w() {
+    check(
+        "along_positive_w",
+        camera(
+            [0., 0., 0., -15.],
+            0.,
+            turn(nalgebra::Vector3::y_axis(), PI),
+        ),
+    );
+}
+
+#[test]
+fn looking_along_y() {
+    check(
+        "along_y",
+        camera(
+            [0., -6., 0., -5.],
+            0.,
+            turn(nalgebra::Vector3::y_axis(), -FRAC_PI_2),
+        ),
+    );
+}
+
+#[test]
+fn looking_along_z() {
+    check(
+        "along_z",
+        camera(
+            [0., 0., -6., -5.],
+            0.,
+            turn(nalgebra::Vector3::x_axis(), FRAC_PI_2),
+        ),
+    );
+}
+
+#[test]
+fn looking_down() {
+    check(
+        "looking_down",
+        camera(
+            [8., 0., 0., -5.],
+            FRAC_PI_2,
+            nalgebra::UnitQuaternion::identity(),
+        ),
+    );
+}
+
+#[test]
+fn looking_up() {
+    check(
+        "looking_up",
+        camera(
+            [-1., 0., 0., -7.5],
+            -FRAC_PI_2,
+            nalgebra::UnitQuaternion::identity(),
+        ),
+    );
+}
+
+#[test]
+fn inside_tree() {
+    check(
+        "inside_tree",
+        camera([1.5, 0., 0., -5.], 0., nalgebra::UnitQuaternion::identity()),
+    );
+}
+
+#[test]
+fn orthographic() {
+    let mut camera = camera([0., 0., 0., 3.], 0., nalgebra::UnitQuaternion::identity());
+    camera.projection = Projection::Orthographic;
+    check("orthographic", camera);
+}
+
+#[test]
+fn narrow_field_of_view() {
+    let mut camera = FourCamera::default();
+    camera.set_fov(0.6);
+    check("narrow_fov", camera);
+}
+
+#[test]
+fn forest() {
+    let world = World {
+        forest: Some(Forest {
+            seed: 4,
+            count: 60,
+            extent: 10.,
+            spacing: 3.,
+            ..Forest::default()
+        }),
+        ..World::default()
+    };
+    check_world(
+        "forest",
+        &world,
+        camera([0.5, 0., 0., 0.], 0.1, nalgebra::UnitQuaternion::identity()),
+    );
+}
+
+#[test]
+fn terrain() {
+    let world = World {
+        terrain: Some(Terrain {
+            seed: 2,
+            amplitude: 1.5,
+            ..Terrain::default()
+        }),
+        ..World::default()
+    };
+    check_world(
+        "terrain",
+        &world,
+        camera(
+            [2.5, 0., 0., 6.],
+            -0.3,
+            nalgebra::UnitQuaternion::identity(),
+        ),
+    );
+}
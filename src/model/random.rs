@@ -0,0 +1,74 @@
+//! A small seedable random number generator, so generated worlds are the same on every platform.
+
+use core::f32::consts::PI;
+
+/// The SplitMix64 generator.
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number uniformly distributed in `[0, 1)`.
+    pub fn uniform(&mut self) -> f32 {
+        // An `f32` has 24 bits of precision.
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// A number uniformly distributed in `[min, max)`.
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.uniform()
+    }
+
+    /// A rotation of 3D space, uniformly distributed over all rotations.
+    pub fn rotation(&mut self) -> nalgebra::UnitQuaternion<f32> {
+        // Shoemake's method.
+        let (u, a, b) = (
+            self.uniform(),
+            2. * PI * self.uniform(),
+            2. * PI * self.uniform(),
+        );
+        let (r, s) = ((1. - u).sqrt(), u.sqrt());
+        nalgebra::UnitQuaternion::new_normalize(nalgebra::Quaternion::new(
+            s * b.cos(),
+            r * a.sin(),
+            r * a.cos(),
+            s * b.sin(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let (mut a, mut b, mut c) = (Random::new(7), Random::new(7), Random::new(8));
+        let a: Vec<_> = (0..10).map(|_| a.next_u64()).collect();
+        let b: Vec<_> = (0..10).map(|_| b.next_u64()).collect();
+        let c: Vec<_> = (0..10).map(|_| c.next_u64()).collect();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn uniform_numbers_fill_the_unit_interval() {
+        let mut random = Random::new(0);
+        let samples: Vec<f32> = (0..10000).map(|_| random.uniform()).collect();
+        assert!(samples.iter().all(|x| (0. ..1.).contains(x)));
+        let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+        assert!((mean - 0.5).abs() < 0.01, "mean {}", mean);
+    }
+}
@@ -0,0 +1,703 @@
+//! The state of the game, independent of the web page it is shown on.
+
+use super::collision::Solids;
+use super::double_quaternion::DoubleQuaternion;
+use super::four_camera::{FourCamera, Orientation, Projection};
+use super::three_camera::ThreeCamera;
+use super::world::World;
+use crate::render::Renderable;
+use core::f32::consts::FRAC_PI_2;
+use std::collections::HashSet;
+
+/// The acceleration due to gravity, in units per second squared.
+const GRAVITY: f32 = 9.8;
+/// The upward speed at the start of a jump.
+const JUMP_SPEED: f32 = 3.;
+/// The top walking speed.
+const WALK_SPEED: f32 = 1.5;
+/// How quickly the player speeds up while walking on the ground.
+const ACCELERATION: f32 = 8.;
+/// How quickly the player slows down on the ground when no key is held.
+const FRICTION: f32 = 6.;
+/// How quickly the player can change course in mid-air.
+const AIR_ACCELERATION: f32 = 1.;
+
+/// The direction gravity pulls against, which `Upright` orientations keep pointing up.
+fn up() -> nalgebra::Vector4<f32> {
+    nalgebra::Vector4::new(1., 0., 0., 0.)
+}
+
+/// A platform-independent input event.
+pub enum Input {
+    KeyDown(String),
+    KeyUp(String),
+    /// The mouse moved by the given number of pixels while the pointer was locked.
+    Look([i32; 2]),
+    /// The mouse wheel scrolled by the given amount while the pointer was locked.
+    Scroll(f64),
+}
+
+pub struct Simulation {
+    keys: HashSet<String>,
+    pub four_camera: FourCamera,
+    pub three_camera: ThreeCamera,
+    solids: Solids,
+    /// Whether the player is stopped by the world's regions, rather than flying through them.
+    pub collisions: bool,
+    /// Whether the player walks under gravity, rather than flying.
+    pub walking: bool,
+    /// The player's velocity while walking.
+    velocity: nalgebra::Vector4<f32>,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new(&World::default().to_renderable())
+    }
+}
+
+impl Simulation {
+    pub fn new(world: &(impl ?Sized + Renderable)) -> Self {
+        Self {
+            keys: HashSet::new(),
+            four_camera: FourCamera::default(),
+            three_camera: ThreeCamera::default(),
+            solids: Solids::new(world.regions()),
+            collisions: true,
+            walking: false,
+            velocity: nalgebra::Vector4::zeros(),
+        }
+    }
+
+    /// Replace the world the player collides with, and return the player to the origin.
+    pub fn set_world(&mut self, world: &(impl ?Sized + Renderable)) {
+        self.solids = Solids::new(world.regions());
+        self.four_camera.position = nalgebra::Vector4::zeros();
+        self.velocity = nalgebra::Vector4::zeros();
+    }
+
+    pub fn input(&mut self, input: Input) {
+        let orientation = &mut self.four_camera.orientation;
+        match input {
+            Input::KeyDown(k) => {
+                let k = k.to_lowercase();
+                // Ignore the repeated events sent while a key is held down.
+                if !self.keys.contains(&k) {
+                    match k.as_str() {
+                        "f" => match orientation {
+                            Orientation::Upright(_) => orientation.free(),
+                            Orientation::Free(_) => orientation.snap_upright(),
+                        },
+                        "c" => self.collisions = !self.collisions,
+                        "g" => {
+                            self.walking = !self.walking;
+                            self.velocity = nalgebra::Vector4::zeros();
+                        }
+                        "p" => {
+                            self.four_camera.projection = match self.four_camera.projection {
+                                Projection::Perspective => Projection::Orthographic,
+                                Projection::Orthographic => Projection::Perspective,
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                self.keys.insert(k);
+            }
+            Input::KeyUp(k) => {
+                self.keys.remove(&k.to_lowercase());
+            }
+            Input::Look([x, y]) => {
+                match orientation {
+                    Orientation::Upright(upright) => {
+                        upright.horizontal *= nalgebra::UnitQuaternion::new(
+                            nalgebra::Vector3::new(0., -x as f32 * 3e-3, 0.),
+                        );
+                        upright.vertical += y as f32 * 3e-3;
+                        upright.vertical = upright.vertical.min(FRAC_PI_2);
+                        upright.vertical = upright.vertical.max(-FRAC_PI_2);
+                    }
+                    Orientation::Free(rotation) => {
+                        *rotation = *rotation
+                            * DoubleQuaternion::plane(1, 3, x as f32 * 3e-3)
+                            * DoubleQuaternion::plane(0, 3, -y as f32 * 3e-3);
+                    }
+                }
+            }
+            Input::Scroll(z) => {
+                match orientation {
+                    Orientation::Upright(upright) => {
+                        upright.horizontal *= nalgebra::UnitQuaternion::new(
+                            nalgebra::Vector3::new(-z as f32 * 1e-2, 0., 0.),
+                        );
+                    }
+                    Orientation::Free(rotation) => {
+                        *rotation = *rotation * DoubleQuaternion::plane(2, 3, -z as f32 * 1e-2);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Advance the simulation by `dt` seconds.
+    pub fn step(&mut self, dt: f64) {
+        self.zoom(dt);
+        self.orbit(dt);
+        self.turn_player(dt);
+        self.move_player(dt);
+    }
+
+    /// Widen or narrow the field of view, by a factor of `e` per second.
+    fn zoom(&mut self, dt: f64) {
+        let mut fov = self.four_camera.fov();
+        if self.keys.contains("-") {
+            fov *= (dt as f32).exp();
+        }
+        if self.keys.contains("=") {
+            fov /= (dt as f32).exp();
+        }
+        self.four_camera.set_fov(fov);
+    }
+
+    /// Turn and zoom the view of the three-screen, and change the distance between the eyes.
+    fn orbit(&mut self, dt: f64) {
+        let dt = dt as f32;
+        let keys = &self.keys;
+        let held = |key| if keys.contains(key) { dt } else { 0. };
+        let camera = &mut self.three_camera;
+
+        camera.rotate(
+            held("arrowright") - held("arrowleft"),
+            held("arrowdown") - held("arrowup"),
+        );
+        camera.zoom((held(".") - held(",")).exp());
+        camera.eye_separation += 0.1 * (held("]") - held("["));
+    }
+
+    /// In free flight, the mouse turns the camera in the three planes containing the direction of view,
+    /// and these keys turn it in the other three.
+    fn turn_player(&mut self, dt: f64) {
+        if let Orientation::Free(rotation) = &mut self.four_camera.orientation {
+            let angle = dt as f32;
+            for &(key, a, b, sign) in &[
+                ("i", 0, 1, 1.),
+                ("k", 0, 1, -1.),
+                ("j", 0, 2, 1.),
+                ("l", 0, 2, -1.),
+                ("u", 1, 2, 1.),
+                ("o", 1, 2, -1.),
+            ] {
+                if self.keys.contains(key) {
+                    *rotation = *rotation * DoubleQuaternion::plane(a, b, sign * angle);
+                }
+            }
+        }
+    }
+
+    fn move_player(&mut self, dt: f64) {
+        let mut direction = nalgebra::Vector4::zeros();
+        if self.keys.contains(" ") {
+            direction += nalgebra::Vector4::new(1., 0., 0., 0.);
+        }
+        if self.keys.contains("shift") {
+            direction += nalgebra::Vector4::new(-1., 0., 0., 0.);
+        }
+        if self.keys.contains("w") {
+            direction += nalgebra::Vector4::new(0., 0., 0., -1.);
+        }
+        if self.keys.contains("s") {
+            direction += nalgebra::Vector4::new(0., 0., 0., 1.);
+        }
+        if self.keys.contains("d") {
+            direction += nalgebra::Vector4::new(0., 1., 0., 0.);
+        }
+        if self.keys.contains("a") {
+            direction += nalgebra::Vector4::new(0., -1., 0., 0.);
+        }
+        if self.keys.contains("q") {
+            direction += nalgebra::Vector4::new(0., 0., 1., 0.);
+        }
+        if self.keys.contains("e") {
+            direction += nalgebra::Vector4::new(0., 0., -1., 0.);
+        }
+
+        let direction = self.four_camera.orientation.movement_mat() * direction;
+        if self.walking {
+            self.walk(dt as f32, direction);
+        } else {
+            self.displace(direction * dt as f32);
+        }
+    }
+
+    /// Accelerate horizontally towards `direction` and fall under gravity, jumping if the jump key is held.
+    ///
+    /// Without collisions there is no ground to land on, so the player walks at a constant height instead.
+    fn walk(&mut self, dt: f32, direction: nalgebra::Vector4<f32>) {
+        let up = up();
+        let supported = !self.collisions || self.solids.supported(&self.four_camera.position, &up);
+
+        let direction = direction - up * up.dot(&direction);
+        let target = direction
+            .try_normalize(1e-6)
+            .map_or_else(nalgebra::Vector4::zeros, |d| d * WALK_SPEED);
+        let rate = if !supported {
+            AIR_ACCELERATION
+        } else if target == nalgebra::Vector4::zeros() {
+            FRICTION
+        } else {
+            ACCELERATION
+        };
+
+        let vertical = up.dot(&self.velocity);
+        let horizontal = self.velocity - up * vertical;
+        let change = target - horizontal;
+        let horizontal = if change.norm() <= rate * dt {
+            target
+        } else {
+            horizontal + change.normalize() * rate * dt
+        };
+
+        let vertical = if !self.collisions {
+            0.
+        } else if supported && self.keys.contains(" ") {
+            JUMP_SPEED
+        } else {
+            vertical - GRAVITY * dt
+        };
+
+        self.velocity = horizontal + up * vertical;
+        let from = self.four_camera.position;
+        self.displace(self.velocity * dt);
+        // Whatever the player ran into took away the velocity towards it.
+        if dt > 0. {
+            self.velocity = (self.four_camera.position - from) / dt;
+        }
+    }
+
+    fn displace(&mut self, displacement: nalgebra::Vector4<f32>) {
+        let position = &mut self.four_camera.position;
+        if self.collisions {
+            *position = self.solids.slide(*position, displacement);
+        } else {
+            *position += displacement;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::collision::RADIUS;
+    use crate::model::four_camera::{Upright, FOV_RANGE};
+
+    fn assert_close(a: nalgebra::Vector4<f32>, b: nalgebra::Vector4<f32>) {
+        assert!((a - b).norm() < 1e-5, "{} is not close to {}", a, b);
+    }
+
+    fn press(simulation: &mut Simulation, key: &str) {
+        simulation.input(Input::KeyDown(key.into()));
+    }
+
+    #[test]
+    fn keys_move_along_their_axes() {
+        for &(key, direction) in &[
+            (" ", [1., 0., 0., 0.]),
+            ("Shift", [-1., 0., 0., 0.]),
+            ("w", [0., 0., 0., -1.]),
+            ("s", [0., 0., 0., 1.]),
+            ("d", [0., 1., 0., 0.]),
+            ("a", [0., -1., 0., 0.]),
+            ("q", [0., 0., 1., 0.]),
+            ("e", [0., 0., -1., 0.]),
+        ] {
+            let mut simulation = Simulation::default();
+            press(&mut simulation, key);
+            simulation.step(0.5);
+            assert_close(
+                simulation.four_camera.position,
+                nalgebra::Vector4::from(direction) * 0.5,
+            );
+        }
+    }
+
+    #[test]
+    fn keys_are_case_insensitive() {
+        let mut simulation = Simulation::default();
+        press(&mut simulation, "W");
+        simulation.input(Input::KeyUp("w".into()));
+        simulation.step(1.);
+        assert_close(simulation.four_camera.position, nalgebra::Vector4::zeros());
+    }
+
+    #[test]
+    fn releasing_a_key_stops_movement() {
+        let mut simulation = Simulation::default();
+        press(&mut simulation, "w");
+        simulation.step(1.);
+        simulation.input(Input::KeyUp("w".into()));
+        simulation.step(1.);
+        assert_close(
+            simulation.four_camera.position,
+            nalgebra::Vector4::new(0., 0., 0., -1.),
+        );
+    }
+
+    #[test]
+    fn turning_changes_the_direction_of_movement() {
+        let mut simulation = Simulation::default();
+        simulation.input(Input::Look([100, 0]));
+        press(&mut simulation, "w");
+        simulation.step(1.);
+
+        let position = simulation.four_camera.position;
+        let (s, c) = 0.3_f32.sin_cos();
+        assert_close(position, nalgebra::Vector4::new(0., s, 0., -c));
+    }
+
+    #[test]
+    fn scrolling_turns_in_the_other_horizontal_plane() {
+        let mut simulation = Simulation::default();
+        simulation.input(Input::Scroll(-30.));
+        press(&mut simulation, "w");
+        simulation.step(1.);
+
+        let position = simulation.four_camera.position;
+        let (s, c) = 0.3_f32.sin_cos();
+        assert_close(position, nalgebra::Vector4::new(0., 0., s, -c));
+    }
+
+    #[test]
+    fn looking_up_and_down_does_not_change_movement() {
+        let mut simulation = Simulation::default();
+        simulation.input(Input::Look([0, 200]));
+        press(&mut simulation, "w");
+        press(&mut simulation, " ");
+        simulation.step(1.);
+        assert_close(
+            simulation.four_camera.position,
+            nalgebra::Vector4::new(1., 0., 0., -1.),
+        );
+    }
+
+    fn upright(simulation: &Simulation) -> &Upright {
+        match &simulation.four_camera.orientation {
+            Orientation::Upright(upright) => upright,
+            Orientation::Free(_) => panic!("not upright"),
+        }
+    }
+
+    #[test]
+    fn vertical_angle_is_clamped() {
+        let mut simulation = Simulation::default();
+        simulation.input(Input::Look([0, 10000]));
+        assert_eq!(upright(&simulation).vertical, FRAC_PI_2);
+        simulation.input(Input::Look([0, -20000]));
+        assert_eq!(upright(&simulation).vertical, -FRAC_PI_2);
+    }
+
+    fn assert_matrix_close(a: nalgebra::Matrix4x5<f32>, b: nalgebra::Matrix4x5<f32>) {
+        assert!((a - b).norm() < 1e-4, "{} is not close to {}", a, b);
+    }
+
+    #[test]
+    fn entering_and_leaving_free_flight_keeps_the_view() {
+        let mut simulation = Simulation::default();
+        simulation.input(Input::Look([120, -80]));
+        simulation.input(Input::Scroll(25.));
+        let view = simulation.four_camera.projection_matrix();
+
+        press(&mut simulation, "f");
+        assert!(matches!(
+            simulation.four_camera.orientation,
+            Orientation::Free(_)
+        ));
+        assert_matrix_close(simulation.four_camera.projection_matrix(), view);
+
+        // Holding the key down does not toggle it again.
+        press(&mut simulation, "f");
+        simulation.input(Input::KeyUp("f".into()));
+        assert!(matches!(
+            simulation.four_camera.orientation,
+            Orientation::Free(_)
+        ));
+
+        press(&mut simulation, "f");
+        assert!(matches!(
+            simulation.four_camera.orientation,
+            Orientation::Upright(_)
+        ));
+        assert_matrix_close(simulation.four_camera.projection_matrix(), view);
+    }
+
+    #[test]
+    fn free_flight_moves_in_the_direction_of_view() {
+        let mut simulation = Simulation::default();
+        press(&mut simulation, "f");
+        simulation.input(Input::Look([0, 100]));
+        press(&mut simulation, "w");
+        simulation.step(1.);
+
+        // Looking down turns the view towards `-x`.
+        let (s, c) = 0.3_f32.sin_cos();
+        assert_close(
+            simulation.four_camera.position,
+            nalgebra::Vector4::new(-s, 0., 0., -c),
+        );
+    }
+
+    #[test]
+    fn free_flight_turns_like_upright_mode_when_level() {
+        let mut upright = Simulation::default();
+        let mut free = Simulation::default();
+        press(&mut free, "f");
+        free.input(Input::KeyUp("f".into()));
+        for simulation in &mut [&mut upright, &mut free] {
+            simulation.input(Input::Look([100, 0]));
+            simulation.input(Input::Scroll(-40.));
+        }
+        assert_matrix_close(
+            upright.four_camera.projection_matrix(),
+            free.four_camera.projection_matrix(),
+        );
+    }
+
+    #[test]
+    fn keys_turn_in_the_planes_not_containing_the_direction_of_view() {
+        for key in &["i", "k", "j", "l", "u", "o"] {
+            let mut simulation = Simulation::default();
+            press(&mut simulation, "f");
+            simulation.input(Input::KeyUp("f".into()));
+            press(&mut simulation, key);
+            simulation.step(0.5);
+
+            let m = simulation.four_camera.orientation.movement_mat();
+            assert_close(
+                m * nalgebra::Vector4::new(0., 0., 0., -1.),
+                nalgebra::Vector4::new(0., 0., 0., -1.),
+            );
+            assert!((m - nalgebra::Matrix4::identity()).norm() > 0.1);
+        }
+    }
+
+    #[test]
+    fn snapping_upright_keeps_the_direction_of_view() {
+        let mut simulation = Simulation::default();
+        press(&mut simulation, "f");
+        simulation.input(Input::KeyUp("f".into()));
+        simulation.input(Input::Look([150, 200]));
+        press(&mut simulation, "i");
+        press(&mut simulation, "u");
+        simulation.step(0.7);
+
+        let forward = |simulation: &Simulation| {
+            simulation.four_camera.orientation.to_rotation().to_matrix()
+                * nalgebra::Vector4::new(0., 0., 0., -1.)
+        };
+        let before = forward(&simulation);
+        press(&mut simulation, "f");
+        assert_close(forward(&simulation), before);
+
+        // Upright again, so the first axis points up on screen.
+        let up = simulation.four_camera.orientation.to_rotation().to_matrix()
+            * nalgebra::Vector4::new(1., 0., 0., 0.);
+        assert!(up[0] > 0.);
+        assert!(upright(&simulation).vertical.abs() < FRAC_PI_2);
+    }
+
+    #[test]
+    fn keys_change_the_field_of_view() {
+        let mut simulation = Simulation::default();
+        let fov = simulation.four_camera.fov();
+
+        press(&mut simulation, "-");
+        simulation.step(0.2);
+        assert!((simulation.four_camera.fov() - fov * 0.2_f32.exp()).abs() < 1e-5);
+        simulation.input(Input::KeyUp("-".into()));
+
+        press(&mut simulation, "=");
+        simulation.step(100.);
+        assert_eq!(simulation.four_camera.fov(), FOV_RANGE.0);
+    }
+
+    #[test]
+    fn key_toggles_projection() {
+        let mut simulation = Simulation::default();
+        press(&mut simulation, "p");
+        assert_eq!(simulation.four_camera.projection, Projection::Orthographic);
+        simulation.input(Input::KeyUp("p".into()));
+        press(&mut simulation, "P");
+        assert_eq!(simulation.four_camera.projection, Projection::Perspective);
+    }
+
+    #[test]
+    fn arrow_keys_orbit_the_three_screen_without_moving_the_player() {
+        let mut simulation = Simulation::default();
+        press(&mut simulation, "ArrowLeft");
+        press(&mut simulation, ",");
+        simulation.step(0.5);
+
+        assert!((simulation.three_camera.yaw + 0.5).abs() < 1e-6);
+        assert!(simulation.three_camera.distance > ThreeCamera::default().distance);
+        assert_close(simulation.four_camera.position, nalgebra::Vector4::zeros());
+    }
+
+    #[test]
+    fn the_player_does_not_enter_tree_trunks() {
+        // Walking along the ground straight into the trunk of the tree at `w = -5`.
+        let mut simulation = Simulation::default();
+        simulation.four_camera.position.x = -1.;
+        press(&mut simulation, "w");
+        for _ in 0..200 {
+            simulation.step(0.05);
+            let p = simulation.four_camera.position;
+            // The trunk is a dodecahedral prism with an inradius of a quarter of the dodecahedron's.
+            let inradius = 0.25 * 1.376;
+            let distance = nalgebra::Vector3::new(p.y, p.z, p.w + 5.).norm();
+            assert!(
+                distance > inradius + RADIUS - 1e-2,
+                "entered the trunk at {}",
+                p
+            );
+        }
+    }
+
+    #[test]
+    fn the_ground_stops_the_player_and_collisions_can_be_turned_off() {
+        let mut simulation = Simulation::default();
+        press(&mut simulation, "Shift");
+        simulation.step(10.);
+        let x = simulation.four_camera.position.x;
+        assert!((x - (-1.5 + RADIUS)).abs() < 1e-2, "stopped at x = {}", x);
+
+        press(&mut simulation, "c");
+        assert!(!simulation.collisions);
+        simulation.step(1.);
+        assert!(simulation.four_camera.position.x < x - 0.9);
+    }
+
+    /// Turn on walking, and let the player fall to the ground.
+    fn land(simulation: &mut Simulation) {
+        press(simulation, "g");
+        simulation.input(Input::KeyUp("g".into()));
+        for _ in 0..100 {
+            simulation.step(0.02);
+        }
+    }
+
+    #[test]
+    fn walking_players_fall_to_the_ground() {
+        let mut simulation = Simulation::default();
+        land(&mut simulation);
+        let x = simulation.four_camera.position.x;
+        assert!((x - (-1.5 + RADIUS)).abs() < 1e-2, "landed at x = {}", x);
+        assert!(simulation.velocity.norm() < 1e-2);
+    }
+
+    #[test]
+    fn walking_without_collisions_keeps_height() {
+        let mut simulation = Simulation::default();
+        land(&mut simulation);
+        press(&mut simulation, "c");
+        let x = simulation.four_camera.position.x;
+
+        press(&mut simulation, "w");
+        press(&mut simulation, " ");
+        for _ in 0..100 {
+            simulation.step(0.02);
+        }
+        assert_eq!(simulation.four_camera.position.x, x);
+        assert_close(
+            simulation.velocity,
+            nalgebra::Vector4::new(0., 0., 0., -WALK_SPEED),
+        );
+    }
+
+    #[test]
+    fn walking_speeds_up_and_slows_down() {
+        let mut simulation = Simulation::default();
+        land(&mut simulation);
+        let start = simulation.four_camera.position;
+
+        press(&mut simulation, "w");
+        simulation.step(0.02);
+        assert!(simulation.velocity.norm() < WALK_SPEED / 2.);
+        for _ in 0..50 {
+            simulation.step(0.02);
+        }
+        assert_close(
+            simulation.velocity,
+            nalgebra::Vector4::new(0., 0., 0., -WALK_SPEED),
+        );
+        // Still on the ground.
+        assert!((simulation.four_camera.position.x - start.x).abs() < 1e-2);
+
+        simulation.input(Input::KeyUp("w".into()));
+        simulation.step(0.02);
+        assert!(simulation.velocity.norm() > WALK_SPEED / 2.);
+        for _ in 0..50 {
+            simulation.step(0.02);
+        }
+        assert_eq!(simulation.velocity, nalgebra::Vector4::zeros());
+    }
+
+    #[test]
+    fn looking_down_does_not_slow_walking() {
+        let mut simulation = Simulation::default();
+        press(&mut simulation, "f");
+        simulation.input(Input::Look([0, 400]));
+        land(&mut simulation);
+
+        press(&mut simulation, "w");
+        for _ in 0..50 {
+            simulation.step(0.02);
+        }
+        assert!((simulation.velocity.norm() - WALK_SPEED).abs() < 1e-3);
+    }
+
+    #[test]
+    fn jumping() {
+        let mut simulation = Simulation::default();
+        land(&mut simulation);
+        let ground = simulation.four_camera.position.x;
+
+        press(&mut simulation, " ");
+        simulation.step(0.02);
+        simulation.input(Input::KeyUp(" ".into()));
+        let mut highest = ground;
+        for _ in 0..100 {
+            simulation.step(0.02);
+            highest = highest.max(simulation.four_camera.position.x);
+        }
+
+        let height = JUMP_SPEED * JUMP_SPEED / (2. * GRAVITY);
+        assert!(
+            (highest - ground - height).abs() < 0.05,
+            "jumped {}",
+            highest - ground
+        );
+        assert!((simulation.four_camera.position.x - ground).abs() < 1e-2);
+    }
+
+    #[test]
+    fn no_jumping_in_mid_air() {
+        let mut simulation = Simulation::default();
+        press(&mut simulation, "g");
+        press(&mut simulation, " ");
+        simulation.step(0.02);
+        assert!(simulation.velocity.x < 0.);
+    }
+
+    #[test]
+    fn loaded_worlds_are_solid() {
+        let mut simulation = Simulation::default();
+        simulation.four_camera.position = nalgebra::Vector4::new(0., 0., 0., -3.);
+        simulation
+            .set_world(&*crate::format::scene::parse("translate 0 0 0 -3  tesseract").unwrap());
+        assert_close(simulation.four_camera.position, nalgebra::Vector4::zeros());
+
+        press(&mut simulation, "w");
+        simulation.step(10.);
+        let w = simulation.four_camera.position.w;
+        assert!((w - (-2.5 + RADIUS)).abs() < 1e-2, "stopped at w = {}", w);
+    }
+}
@@ -0,0 +1,373 @@
+//! Hilly ground, generated from a noise heightfield over the horizontal axes `(y, z, w)`.
+//!
+//! The heightfield is sampled on a grid of cubes, each split into six tetrahedra along its main diagonal.
+//! The surface is linear over each tetrahedron, and the solid ground beneath it is the union of
+//! one convex region per tetrahedron, so the surface and the regions agree exactly.
+//!
+//! Like the flat ground, only the boundary of the surface is drawn, so that how dark it looks
+//! does not depend on how finely it is divided.
+
+use super::random::Random;
+use crate::format::mesh::{Corner, Mesh};
+use std::collections::HashMap;
+
+/// The texel painting the terrain, which is the color of the flat ground.
+const TEXCOORD: [f32; 2] = [1., 1.];
+/// The number of octaves of noise, each with half the wavelength and amplitude of the last.
+const OCTAVES: i32 = 3;
+
+#[derive(Clone, Debug)]
+pub struct Terrain {
+    pub seed: u64,
+    /// The terrain covers the cube `[-extent, extent]^3` of horizontal space.
+    pub extent: f32,
+    /// The number of grid cells along each horizontal axis. Zero is treated as one.
+    pub resolution: usize,
+    /// How far the surface may rise above or fall below zero.
+    pub amplitude: f32,
+    /// The horizontal distance between neighboring hills.
+    pub wavelength: f32,
+    /// How far below zero the solid ground extends.
+    pub depth: f32,
+}
+
+impl Default for Terrain {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            extent: 10.,
+            resolution: 10,
+            amplitude: 1.,
+            wavelength: 6.,
+            depth: 2.,
+        }
+    }
+}
+
+impl Terrain {
+    fn cells(&self) -> usize {
+        self.resolution.max(1)
+    }
+
+    fn cell_size(&self) -> f32 {
+        2. * self.extent / self.cells() as f32
+    }
+
+    /// The horizontal position of a grid vertex.
+    fn grid_point(&self, index: [usize; 3]) -> nalgebra::Vector3<f32> {
+        nalgebra::Vector3::from_iterator(
+            index
+                .iter()
+                .map(|&i| i as f32 * self.cell_size() - self.extent),
+        )
+    }
+
+    /// The height of the surface at a grid vertex.
+    fn grid_height(&self, index: [usize; 3]) -> f32 {
+        let p = self.grid_point(index);
+        // Flatten the terrain around the origin, where the player starts.
+        let flatten = (p.norm() / self.wavelength).min(1.);
+        self.amplitude * flatten * fractal_noise(self.seed, p / self.wavelength)
+    }
+
+    /// The height of the surface above the horizontal position `p`, clamped to the terrain's extent.
+    pub fn height(&self, p: &nalgebra::Vector3<f32>) -> f32 {
+        let grid = p.map(|x| {
+            ((x + self.extent) / self.cell_size())
+                .max(0.)
+                .min(self.cells() as f32)
+        });
+        let cell = grid.map(|x| (x.floor() as usize).min(self.cells() - 1));
+        let fraction = grid - cell.map(|i| i as f32);
+
+        // Walk from the cell's lowest corner along the axes in decreasing order of `fraction`,
+        // through the vertices of the tetrahedron containing `p`.
+        let order = sorted_axes(&fraction);
+        let mut corner = [cell[0], cell[1], cell[2]];
+        let mut height = (1. - fraction[order[0]]) * self.grid_height(corner);
+        for (k, &axis) in order.iter().enumerate() {
+            corner[axis] += 1;
+            let next = order.get(k + 1).map_or(0., |&a| fraction[a]);
+            height += (fraction[axis] - next) * self.grid_height(corner);
+        }
+        height
+    }
+
+    pub fn to_mesh(&self) -> Mesh {
+        let n = self.cells() + 1;
+        let index = |[i, j, k]: [usize; 3]| (i * n + j) * n + k;
+
+        let mut mesh = Mesh::default();
+        mesh.texcoords.push(TEXCOORD);
+        for i in 0..n {
+            for j in 0..n {
+                for k in 0..n {
+                    let height = self.grid_height([i, j, k]);
+                    mesh.positions
+                        .push(self.grid_point([i, j, k]).insert_row(0, height));
+                }
+            }
+        }
+
+        // How many tetrahedra each face belongs to.
+        let mut faces = HashMap::new();
+        for i in 0..self.cells() {
+            for j in 0..self.cells() {
+                for k in 0..self.cells() {
+                    for order in &PERMUTATIONS {
+                        let mut corner = [i, j, k];
+                        let mut tetrahedron = [index(corner); 4];
+                        for (v, &axis) in tetrahedron[1..].iter_mut().zip(order) {
+                            corner[axis] += 1;
+                            *v = index(corner);
+                        }
+
+                        let points: Vec<_> =
+                            tetrahedron.iter().map(|&v| mesh.positions[v]).collect();
+                        mesh.regions.push(self.region(&points));
+
+                        for skip in 0..4 {
+                            let mut face: Vec<usize> = (0..4)
+                                .filter(|&v| v != skip)
+                                .map(|v| tetrahedron[v])
+                                .collect();
+                            face.sort_unstable();
+                            *faces.entry([face[0], face[1], face[2]]).or_insert(0) += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Faces inside the surface are shared by two tetrahedra, and those on its boundary belong to only one.
+        let mut faces: Vec<_> = faces
+            .into_iter()
+            .filter(|&(_, count)| count == 1)
+            .map(|(face, _)| face)
+            .collect();
+        faces.sort_unstable();
+        for face in faces {
+            // Triangles are lit as if they were part of the ground beneath them, facing up and away from it.
+            let points = [
+                mesh.positions[face[0]],
+                mesh.positions[face[1]],
+                mesh.positions[face[2]],
+            ];
+            let below = (points[0] + points[1] + points[2]) / 3. - nalgebra::Vector4::x();
+            mesh.normals.push(crate::render::normal(points, below));
+
+            let corner = |position| Corner {
+                position,
+                texcoord: Some(0),
+                normal: Some(mesh.normals.len() - 1),
+            };
+            mesh.triangles
+                .push([corner(face[0]), corner(face[1]), corner(face[2])]);
+        }
+
+        mesh
+    }
+
+    /// The solid ground beneath a tetrahedron of the surface, down to `-depth`.
+    fn region(&self, points: &[nalgebra::Vector4<f32>]) -> Vec<nalgebra::RowVector5<f32>> {
+        let horizontal: Vec<_> = points.iter().map(|p| p.remove_row(0)).collect();
+
+        // The surface is `x = gradient . p + offset` over the tetrahedron.
+        let edges = nalgebra::Matrix3::from_rows(&[
+            (horizontal[1] - horizontal[0]).transpose(),
+            (horizontal[2] - horizontal[0]).transpose(),
+            (horizontal[3] - horizontal[0]).transpose(),
+        ]);
+        let rises = nalgebra::Vector3::new(
+            points[1][0] - points[0][0],
+            points[2][0] - points[0][0],
+            points[3][0] - points[0][0],
+        );
+        let gradient = edges
+            .lu()
+            .solve(&rises)
+            .expect("grid tetrahedra are not degenerate");
+        let offset = points[0][0] - gradient.dot(&horizontal[0]);
+
+        let mut region = vec![
+            nalgebra::RowVector5::new(1., -gradient[0], -gradient[1], -gradient[2], -offset),
+            nalgebra::RowVector5::new(-1., 0., 0., 0., -self.depth),
+        ];
+
+        // The vertical walls through the faces of the tetrahedron.
+        for skip in 0..4 {
+            let face: Vec<_> = (0..4)
+                .filter(|&v| v != skip)
+                .map(|v| horizontal[v])
+                .collect();
+            let mut normal = (face[1] - face[0]).cross(&(face[2] - face[0]));
+            if normal.dot(&(horizontal[skip] - face[0])) > 0. {
+                normal = -normal;
+            }
+            region.push(nalgebra::RowVector5::new(
+                0.,
+                normal[0],
+                normal[1],
+                normal[2],
+                -normal.dot(&face[0]),
+            ));
+        }
+
+        region
+    }
+}
+
+/// The orders in which a path through a cube's tetrahedra can take steps along the three axes.
+const PERMUTATIONS: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+/// The axes, sorted so that the components of `v` decrease.
+fn sorted_axes(v: &nalgebra::Vector3<f32>) -> [usize; 3] {
+    let mut axes = [0, 1, 2];
+    axes.sort_by(|&a, &b| v[b].total_cmp(&v[a]));
+    axes
+}
+
+/// Smooth noise in `[-1, 1]`, summed over several octaves.
+fn fractal_noise(seed: u64, p: nalgebra::Vector3<f32>) -> f32 {
+    let mut total = 0.;
+    let mut weights = 0.;
+    for octave in 0..OCTAVES {
+        let weight = 0.5f32.powi(octave);
+        total += weight * value_noise(seed.wrapping_add(octave as u64), p / weight);
+        weights += weight;
+    }
+    total / weights
+}
+
+/// Random values at the integer lattice points, smoothly interpolated between them.
+fn value_noise(seed: u64, p: nalgebra::Vector3<f32>) -> f32 {
+    let base = p.map(f32::floor);
+    let t = (p - base).map(|t| t * t * (3. - 2. * t));
+
+    let mut total = 0.;
+    for corner in 0..8 {
+        let mut weight = 1.;
+        let mut lattice = [0; 3];
+        for axis in 0..3 {
+            let bit = (corner >> axis) & 1;
+            weight *= if bit == 1 { t[axis] } else { 1. - t[axis] };
+            lattice[axis] = base[axis] as i64 + bit as i64;
+        }
+        total += weight * lattice_value(seed, lattice);
+    }
+    total
+}
+
+fn lattice_value(seed: u64, [i, j, k]: [i64; 3]) -> f32 {
+    let hash = (i as u64).wrapping_mul(0x8da6_b343)
+        ^ (j as u64).wrapping_mul(0xd816_3841)
+        ^ (k as u64).wrapping_mul(0xcb1a_b31f);
+    Random::new(seed ^ hash).range(-1., 1.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::Renderable;
+
+    fn terrain() -> Terrain {
+        Terrain {
+            seed: 3,
+            resolution: 4,
+            amplitude: 2.,
+            ..Terrain::default()
+        }
+    }
+
+    fn inside(region: &[nalgebra::RowVector5<f32>], p: nalgebra::Vector4<f32>) -> bool {
+        region.iter().all(|h| (h * p.insert_row(4, 1.))[0] < 0.)
+    }
+
+    #[test]
+    fn terrain_is_reproducible_and_has_hills() {
+        let heights = |terrain: &Terrain| -> Vec<f32> {
+            terrain.to_mesh().positions.iter().map(|p| p[0]).collect()
+        };
+        let a = heights(&terrain());
+        assert_eq!(a, heights(&terrain()));
+        assert_ne!(
+            a,
+            heights(&Terrain {
+                seed: 4,
+                ..terrain()
+            })
+        );
+        assert!(a.iter().any(|&h| h > 0.2) && a.iter().any(|&h| h < -0.2));
+        assert_eq!(terrain().height(&nalgebra::Vector3::zeros()), 0.);
+    }
+
+    #[test]
+    fn zero_resolution_is_one_cell() {
+        let flat = Terrain {
+            resolution: 0,
+            ..terrain()
+        };
+        let coarse = Terrain {
+            resolution: 1,
+            ..terrain()
+        };
+        assert_eq!(flat.to_mesh(), coarse.to_mesh());
+        let p = nalgebra::Vector3::new(1., 2., 3.);
+        assert_eq!(flat.height(&p), coarse.height(&p));
+    }
+
+    #[test]
+    fn non_finite_positions_are_clamped() {
+        let terrain = terrain();
+        let height = |x: f32| terrain.height(&nalgebra::Vector3::new(x, 1., 2.));
+        assert_eq!(height(f32::NAN), height(-terrain.extent));
+        assert_eq!(height(f32::INFINITY), height(terrain.extent));
+        assert_eq!(height(f32::NEG_INFINITY), height(-terrain.extent));
+    }
+
+    #[test]
+    fn triangles_lie_on_the_surface() {
+        let terrain = terrain();
+        let mesh = terrain.to_mesh();
+        // Only the boundary is drawn: six faces of the whole grid, each n by n squares split in two.
+        let n = terrain.resolution;
+        assert_eq!(mesh.triangles.len(), 12 * n * n);
+
+        for v in mesh.triangles() {
+            let height = terrain.height(&v.pos.remove_row(0));
+            assert!((v.pos[0] - height).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn regions_fill_the_ground_beneath_the_surface() {
+        let terrain = terrain();
+        let regions = terrain.to_mesh().regions;
+        let mut random = Random::new(0);
+
+        for _ in 0..200 {
+            let p = nalgebra::Vector3::new(
+                random.range(-9.9, 9.9),
+                random.range(-9.9, 9.9),
+                random.range(-9.9, 9.9),
+            );
+            let height = terrain.height(&p);
+            let count = |x: f32| {
+                regions
+                    .iter()
+                    .filter(|r| inside(r, p.insert_row(0, x)))
+                    .count()
+            };
+            assert_eq!(count(height - 1e-2), 1, "below the surface at {}", p);
+            assert_eq!(count(height + 1e-2), 0, "above the surface at {}", p);
+            assert_eq!(count(-terrain.depth - 1e-2), 0);
+        }
+    }
+}
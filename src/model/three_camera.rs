@@ -0,0 +1,132 @@
+//! The camera viewing the three-screen, the 3D image produced by the four-camera.
+
+use core::f32::consts::FRAC_PI_2;
+
+/// How far in front of each eye the near clipping plane is.
+const NEAR: f32 = 0.01;
+
+/// A pair of cameras orbiting the center of the three-screen, one for each eye.
+pub struct ThreeCamera {
+    /// The angle by which the three-screen is turned about its vertical axis.
+    pub yaw: f32,
+    /// The angle by which the three-screen is tilted towards the viewer, from `-pi/2` to `pi/2`.
+    pub pitch: f32,
+    /// The distance from the center of the three-screen to the cameras.
+    pub distance: f32,
+    /// The distance between the eyes. Negative values swap the views, for cross-eyed viewing.
+    pub eye_separation: f32,
+}
+
+impl Default for ThreeCamera {
+    fn default() -> Self {
+        Self {
+            yaw: 0.,
+            pitch: 0.,
+            distance: 3.,
+            eye_separation: 0.1,
+        }
+    }
+}
+
+impl ThreeCamera {
+    pub fn rotate(&mut self, yaw: f32, pitch: f32) {
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-FRAC_PI_2, FRAC_PI_2);
+    }
+
+    /// Move the cameras closer to the center by the given factor.
+    pub fn zoom(&mut self, factor: f32) {
+        self.distance = (self.distance / factor).clamp(0.5, 20.);
+    }
+
+    /// The view-projection matrices for the left and right eyes.
+    pub fn matrices(&self) -> [nalgebra::Matrix4<f32>; 2] {
+        [self.matrix(-0.5), self.matrix(0.5)]
+    }
+
+    /// The view-projection matrix for an eye displaced sideways by `side` times the eye separation.
+    #[rustfmt::skip]
+    fn matrix(&self, side: f32) -> nalgebra::Matrix4<f32> {
+        let offset = side * self.eye_separation;
+
+        let rotation = nalgebra::Rotation3::from_axis_angle(&nalgebra::Vector3::x_axis(), self.pitch)
+            * nalgebra::Rotation3::from_axis_angle(&nalgebra::Vector3::y_axis(), -self.yaw);
+        let view = nalgebra::Translation3::new(-offset, 0., -self.distance).to_homogeneous()
+            * rotation.to_homogeneous();
+
+        // The frustum is sheared, so that the two eyes' views coincide at the center of the three-screen.
+        let projection = nalgebra::Matrix4::new(
+            1., 0., -offset / self.distance, 0.,
+            0., 1., 0., 0.,
+            0., 0., -1., -2. * NEAR,
+            0., 0., -1., 0.,
+        );
+
+        projection * view
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(m: &nalgebra::Matrix4<f32>, p: [f32; 3]) -> nalgebra::Vector3<f32> {
+        let p = m * nalgebra::Vector4::new(p[0], p[1], p[2], 1.);
+        p.xyz() / p.w
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn head_on_without_stereo() {
+        let camera = ThreeCamera {
+            eye_separation: 0.,
+            ..ThreeCamera::default()
+        };
+        let expected = nalgebra::Matrix4::new(
+            1., 0., 0., 0.,
+            0., 1., 0., 0.,
+            0., 0., -1., 2.98,
+            0., 0., -1., 3.,
+        );
+        for m in &camera.matrices() {
+            assert!((m - expected).norm() < 1e-6, "{}", m);
+        }
+    }
+
+    #[test]
+    fn eyes_agree_at_the_center() {
+        let camera = ThreeCamera::default();
+        let [left, right] = camera.matrices();
+
+        let (l, r) = (project(&left, [0.; 3]), project(&right, [0.; 3]));
+        assert!((l.xy() - r.xy()).norm() < 1e-6);
+
+        // Nearer points appear further right to the left eye.
+        let (l, r) = (project(&left, [0., 0., 1.]), project(&right, [0., 0., 1.]));
+        assert!(l.x > r.x);
+    }
+
+    #[test]
+    fn orbiting_turns_the_three_screen() {
+        let mut camera = ThreeCamera {
+            eye_separation: 0.,
+            ..ThreeCamera::default()
+        };
+        camera.rotate(FRAC_PI_2, 0.);
+        // Turned a quarter turn, the three-screen's `x` axis points towards the viewer.
+        let p = project(&camera.matrices()[0], [1., 0., 0.]);
+        assert!(p.xy().norm() < 1e-6);
+
+        camera.rotate(0., 10.);
+        assert_eq!(camera.pitch, FRAC_PI_2);
+    }
+
+    #[test]
+    fn zooming_is_clamped() {
+        let mut camera = ThreeCamera::default();
+        camera.zoom(2.);
+        assert_eq!(camera.distance, 1.5);
+        camera.zoom(1e-3);
+        assert_eq!(camera.distance, 20.);
+    }
+}
@@ -163,7 +163,7 @@ use crate::render::Vertex;
 //     })
 // }
 
-fn icosahedral_group() -> impl Iterator<Item = nalgebra::UnitQuaternion<f32>> {
+pub fn icosahedral_group() -> impl Iterator<Item = nalgebra::UnitQuaternion<f32>> {
     fn f(axis: [f32; 3], angle: f32) -> nalgebra::UnitQuaternion<f32> {
         nalgebra::UnitQuaternion::new(nalgebra::Vector3::from(axis).normalize() * angle)
     }
@@ -223,6 +223,22 @@ fn icosahedral_group() -> impl Iterator<Item = nalgebra::UnitQuaternion<f32>> {
         .chain(rectangles.iter().map(|v| f(*v, PI)))
 }
 
+/// The vertices of the dodecahedron whose face normals are the axes of `icosahedral_group`'s five-fold rotations.
+fn dodecahedron() -> Vec<nalgebra::Vector3<f32>> {
+    let mut out = Vec::new();
+    for &a in &[-1., 1.] {
+        for &b in &[-1., 1.] {
+            for &c in &[-1., 1.] {
+                out.push(nalgebra::Vector3::new(a, b, c));
+            }
+            out.push(nalgebra::Vector3::new(0., a * (PHI - 1.), b * PHI));
+            out.push(nalgebra::Vector3::new(b * PHI, 0., a * (PHI - 1.)));
+            out.push(nalgebra::Vector3::new(a * (PHI - 1.), b * PHI, 0.));
+        }
+    }
+    out
+}
+
 const PHI: f32 = 1.618_034;
 
 /*
@@ -242,85 +258,195 @@ const PHI: f32 = 1.618_034;
 
 */
 
-use crate::render::{Renderable, Transform};
+use super::forest::Forest;
+use super::terrain::Terrain;
+use crate::polytope::Polytope;
+use crate::render::{normal, Renderable, Texture, Textured, Transform};
+use std::rc::Rc;
 
 #[derive(Default)]
-pub struct World;
+pub struct World {
+    /// A generated forest, replacing the two trees standing in front of the player.
+    pub forest: Option<Forest>,
+    /// Hilly ground, replacing the flat ground.
+    pub terrain: Option<Terrain>,
+}
 
 impl World {
+    /// The ground and each tree, with the homogeneous matrix placing it in the world.
+    pub fn objects(&self) -> Vec<(Box<dyn Renderable>, nalgebra::Matrix5<f32>)> {
+        let height = |p: &nalgebra::Vector3<f32>| self.terrain.as_ref().map_or(0., |t| t.height(p));
+        // The player's eyes are above the ground.
+        let lowered = nalgebra::Translation {
+            vector: nalgebra::Vector4::new(-1.5, 0., 0., 0.),
+        }
+        .to_homogeneous();
+
+        let ground: Box<dyn Renderable> = match (&self.terrain, &self.forest) {
+            (Some(terrain), _) => Box::new(terrain.to_mesh()),
+            (None, Some(forest)) => {
+                // Stretch the ground to leave a margin around the forest.
+                let size = (forest.extent + forest.spacing) / GROUND_SIZE;
+                Box::new(
+                    Transform::nonuniform_scaling(
+                        Ground,
+                        nalgebra::Vector4::new(1., size, size, size),
+                    )
+                    .expect("forests have a positive extent"),
+                )
+            }
+            (None, None) => Box::new(Ground),
+        };
+        let ground = Textured {
+            inner: ground,
+            texture: Texture::named("ground").expect("the ground texture exists"),
+        };
+
+        let trees = match &self.forest {
+            Some(forest) => forest.transforms(height),
+            None => [-5., -10.]
+                .iter()
+                .map(|&w| {
+                    let position = nalgebra::Vector3::new(0., 0., w);
+                    nalgebra::Translation {
+                        vector: position.insert_row(0, height(&position)),
+                    }
+                    .to_homogeneous()
+                })
+                .collect(),
+        };
+
+        let tree = Tree::default();
+        let mut objects: Vec<(Box<dyn Renderable>, _)> = vec![(Box::new(ground), lowered)];
+        objects.extend(
+            trees
+                .into_iter()
+                .map(|m| (Box::new(tree.clone()) as Box<dyn Renderable>, lowered * m)),
+        );
+        objects
+    }
+
+    /// All of `objects`, in place.
     pub fn to_renderable(&self) -> impl Renderable {
-        Transform::translation(
-            (
-                Ground,
-                (
-                    Transform::translation(Tree, nalgebra::Vector4::new(0., 0., 0., -5.)),
-                    Transform::translation(Tree, nalgebra::Vector4::new(0., 0., 0., -10.)),
-                ),
-            ),
-            nalgebra::Vector4::new(-1.5, 0., 0., 0.),
-        )
+        self.objects()
+            .into_iter()
+            .map(|(object, m)| {
+                Transform::new(object, m).expect("objects are placed by invertible matrices")
+            })
+            .collect::<Vec<_>>()
     }
 }
 
+/// Half the side length of the ground.
+const GROUND_SIZE: f32 = 10.;
+
 struct Ground;
 
 impl Renderable for Ground {
     #[rustfmt::skip]
     fn triangles(&self) -> Box<dyn Iterator<Item = Vertex>> {
         Box::new(vec![
-            Vertex { pos: nalgebra::Vector4::new(0., -10., -10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10., -10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10., -10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10., -10.), texcoord: [1., 1.] },
-
-            Vertex { pos: nalgebra::Vector4::new(0., -10., -10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10., -10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10., -10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10., -10.), texcoord: [1., 1.] },
-
-            Vertex { pos: nalgebra::Vector4::new(0., -10., -10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10., -10., -10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10., -10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10.,  10.), texcoord: [1., 1.] },
-            Vertex { pos: nalgebra::Vector4::new(0., -10., -10.,  10.), texcoord: [1., 1.] },
+            Vertex { pos: nalgebra::Vector4::new(0., -10., -10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10., -10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10., -10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+
+            Vertex { pos: nalgebra::Vector4::new(0., -10., -10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10., -10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10., -10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+
+            Vertex { pos: nalgebra::Vector4::new(0., -10., -10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10., -10., -10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10., -10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10.,  10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10.,  10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0.,  10., -10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
+            Vertex { pos: nalgebra::Vector4::new(0., -10., -10.,  10.), texcoord: [1., 1.], texture: None, color: [1.; 4], normal: nalgebra::Vector4::x() },
         ]
         .into_iter())
     }
 
     fn regions(&self) -> Box<dyn Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
-        Box::new(std::iter::once(vec![nalgebra::RowVector5::new(
-            0., 0., 0., 0., -1.,
-        )]))
+        // A slab one unit thick under the ground's surface.
+        #[rustfmt::skip]
+        let slab = vec![
+            nalgebra::RowVector5::new( 1.,  0.,  0.,  0.,  0.),
+            nalgebra::RowVector5::new(-1.,  0.,  0.,  0., -1.),
+            nalgebra::RowVector5::new( 0.,  1.,  0.,  0., -10.),
+            nalgebra::RowVector5::new( 0., -1.,  0.,  0., -10.),
+            nalgebra::RowVector5::new( 0.,  0.,  1.,  0., -10.),
+            nalgebra::RowVector5::new( 0.,  0., -1.,  0., -10.),
+            nalgebra::RowVector5::new( 0.,  0.,  0.,  1., -10.),
+            nalgebra::RowVector5::new( 0.,  0.,  0., -1., -10.),
+        ];
+        Box::new(std::iter::once(slab))
     }
 }
 
-struct Tree;
+/// A tree standing on the ground at the origin.
+///
+/// Its regions are the convex hulls of its foliage and trunk, which are computed once and shared between clones.
+#[derive(Clone)]
+pub struct Tree {
+    regions: Rc<[Vec<nalgebra::RowVector5<f32>>]>,
+    foliage: Rc<Texture>,
+    bark: Rc<Texture>,
+}
+
+impl Default for Tree {
+    fn default() -> Self {
+        let base = dodecahedron();
+
+        let foliage: Vec<_> = std::iter::once(nalgebra::Vector4::new(6., 0., 0., 0.))
+            .chain(base.iter().map(|v| v.insert_row(0, 1.)))
+            .collect();
+        let trunk: Vec<_> = base
+            .iter()
+            .flat_map(|v| vec![(v * 0.25).insert_row(0, 0.), (v * 0.25).insert_row(0, 1.)])
+            .collect();
+
+        let regions: Vec<_> = vec![foliage, trunk]
+            .into_iter()
+            .map(|points| {
+                Polytope::hull(&points)
+                    .expect("the tree is not flat")
+                    .facets()
+                    .to_vec()
+            })
+            .collect();
+        Self {
+            regions: regions.into(),
+            foliage: Texture::named("foliage").expect("the foliage texture exists"),
+            bark: Texture::named("bark").expect("the bark texture exists"),
+        }
+    }
+}
 
 impl Renderable for Tree {
     fn triangles(&self) -> Box<dyn Iterator<Item = Vertex>> {
+        let (foliage, bark) = (Rc::clone(&self.foliage), Rc::clone(&self.bark));
         Box::new(icosahedral_group().flat_map(move |q| {
             let mut m = q
                 .to_rotation_matrix()
@@ -329,131 +455,171 @@ impl Renderable for Tree {
                 .insert_column(0, 0.);
             m[(0, 0)] = 1.;
 
-            vec![
+            let mut vertices = vec![
                 // Foliage
                 Vertex {
                     pos: m * nalgebra::Vector4::new(6., 0., 0., 0.),
                     texcoord: [1., 1.],
+                    texture: Some(Rc::clone(&foliage)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(1., 1. - PHI, PHI, 0.),
                     texcoord: [0., 1.],
+                    texture: Some(Rc::clone(&foliage)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(1., PHI - 1., PHI, 0.),
                     texcoord: [0., 1.],
+                    texture: Some(Rc::clone(&foliage)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(1., 0., 0.6 * PHI + 0.2, 0.2 * PHI + 0.4),
                     texcoord: [0., 1.],
+                    texture: Some(Rc::clone(&foliage)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(1., 1. - PHI, PHI, 0.),
                     texcoord: [0., 1.],
+                    texture: Some(Rc::clone(&foliage)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(1., PHI - 1., PHI, 0.),
                     texcoord: [0., 1.],
+                    texture: Some(Rc::clone(&foliage)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(1., 0., 0.6 * PHI + 0.2, 0.2 * PHI + 0.4),
                     texcoord: [0., 1.],
+                    texture: Some(Rc::clone(&foliage)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(1., 1. - PHI, PHI, 0.),
                     texcoord: [0., 1.],
+                    texture: Some(Rc::clone(&foliage)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(1., PHI - 1., PHI, 0.),
                     texcoord: [0., 1.],
+                    texture: Some(Rc::clone(&foliage)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 // Trunk
                 Vertex {
                     pos: m * nalgebra::Vector4::new(0., 0., 0.15 * PHI + 0.05, 0.05 * PHI + 0.1),
                     texcoord: [0., 0.],
+                    texture: Some(Rc::clone(&bark)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(0., 0.25 - 0.25 * PHI, 0.25 * PHI, 0.),
-                    texcoord: [55. / 64., 0.],
+                    texcoord: [1., 0.],
+                    texture: Some(Rc::clone(&bark)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(0., 0.25 * PHI - 0.25, 0.25 * PHI, 0.),
-                    texcoord: [0., 55. / 64.],
+                    texcoord: [0., 1.],
+                    texture: Some(Rc::clone(&bark)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(0., 0., 0.15 * PHI + 0.05, 0.05 * PHI + 0.1),
                     texcoord: [0., 0.],
+                    texture: Some(Rc::clone(&bark)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(0., 0.25 - 0.25 * PHI, 0.25 * PHI, 0.),
-                    texcoord: [55. / 64., 0.],
+                    texcoord: [1., 0.],
+                    texture: Some(Rc::clone(&bark)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(0., 0.25 * PHI - 0.25, 0.25 * PHI, 0.),
-                    texcoord: [0., 55. / 64.],
+                    texcoord: [0., 1.],
+                    texture: Some(Rc::clone(&bark)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(0., 0.25 - 0.25 * PHI, 0.25 * PHI, 0.),
-                    texcoord: [55. / 64., 0.],
+                    texcoord: [1., 0.],
+                    texture: Some(Rc::clone(&bark)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(0., 0.25 * PHI - 0.25, 0.25 * PHI, 0.),
-                    texcoord: [0., 55. / 64.],
+                    texcoord: [0., 1.],
+                    texture: Some(Rc::clone(&bark)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(1., 0.25 * PHI - 0.25, 0.25 * PHI, 0.),
-                    texcoord: [0., 55. / 64.],
+                    texcoord: [0., 1.],
+                    texture: Some(Rc::clone(&bark)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(1., 0.25 * PHI - 0.25, 0.25 * PHI, 0.),
-                    texcoord: [0., 55. / 64.],
+                    texcoord: [0., 1.],
+                    texture: Some(Rc::clone(&bark)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(1., 0.25 - 0.25 * PHI, 0.25 * PHI, 0.),
-                    texcoord: [55. / 64., 0.],
+                    texcoord: [1., 0.],
+                    texture: Some(Rc::clone(&bark)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
                 Vertex {
                     pos: m * nalgebra::Vector4::new(0., 0.25 - 0.25 * PHI, 0.25 * PHI, 0.),
-                    texcoord: [55. / 64., 0.],
+                    texcoord: [1., 0.],
+                    texture: Some(Rc::clone(&bark)),
+                    color: [1.; 4],
+                    normal: nalgebra::Vector4::zeros(),
                 },
-            ]
-            .into_iter()
+            ];
+
+            // The first three triangles bound the foliage, and the rest the trunk.
+            for (i, triangle) in vertices.chunks_mut(3).enumerate() {
+                let inside = nalgebra::Vector4::new(if i < 3 { 2. } else { 0.5 }, 0., 0., 0.);
+                let normal = normal([triangle[0].pos, triangle[1].pos, triangle[2].pos], inside);
+                for v in triangle {
+                    v.normal = normal;
+                }
+            }
+            vertices.into_iter()
         }))
     }
 
-    fn regions(&self) -> Box<dyn Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
-        let foliage = vec![
-            nalgebra::RowVector5::new(-1., 0., 0., 0., 1.),
-            nalgebra::RowVector5::new(0.2 * (PHI + 1.), PHI, 1., 0., -0.2 * (PHI + 1.) * 6.),
-            nalgebra::RowVector5::new(0.2 * (PHI + 1.), PHI, -1., 0., -0.2 * (PHI + 1.) * 6.),
-            nalgebra::RowVector5::new(0.2 * (PHI + 1.), -PHI, 1., 0., -0.2 * (PHI + 1.) * 6.),
-            nalgebra::RowVector5::new(0.2 * (PHI + 1.), -PHI, -1., 0., -0.2 * (PHI + 1.) * 6.),
-            nalgebra::RowVector5::new(0.2 * (PHI + 1.), 1., 0., PHI, -0.2 * (PHI + 1.) * 6.),
-            nalgebra::RowVector5::new(0.2 * (PHI + 1.), -1., 0., PHI, -0.2 * (PHI + 1.) * 6.),
-            nalgebra::RowVector5::new(0.2 * (PHI + 1.), 1., 0., -PHI, -0.2 * (PHI + 1.) * 6.),
-            nalgebra::RowVector5::new(0.2 * (PHI + 1.), -1., 0., -PHI, -0.2 * (PHI + 1.) * 6.),
-            nalgebra::RowVector5::new(0.2 * (PHI + 1.), 0., PHI, 1., -0.2 * (PHI + 1.) * 6.),
-            nalgebra::RowVector5::new(0.2 * (PHI + 1.), 0., PHI, -1., -0.2 * (PHI + 1.) * 6.),
-            nalgebra::RowVector5::new(0.2 * (PHI + 1.), 0., -PHI, 1., -0.2 * (PHI + 1.) * 6.),
-            nalgebra::RowVector5::new(0.2 * (PHI + 1.), 0., -PHI, -1., -0.2 * (PHI + 1.) * 6.),
-        ];
-        let trunk = vec![
-            nalgebra::RowVector5::new(-1., 0., 0., 0., 0.),
-            nalgebra::RowVector5::new(1., 0., 0., 0., -1.),
-            nalgebra::RowVector5::new(0., PHI, 1., 0., -0.25 * (PHI + 1.)),
-            nalgebra::RowVector5::new(0., PHI, -1., 0., -0.25 * (PHI + 1.)),
-            nalgebra::RowVector5::new(0., -PHI, 1., 0., -0.25 * (PHI + 1.)),
-            nalgebra::RowVector5::new(0., -PHI, -1., 0., -0.25 * (PHI + 1.)),
-            nalgebra::RowVector5::new(0., 1., 0., PHI, -0.25 * (PHI + 1.)),
-            nalgebra::RowVector5::new(0., -1., 0., PHI, -0.25 * (PHI + 1.)),
-            nalgebra::RowVector5::new(0., 1., 0., -PHI, -0.25 * (PHI + 1.)),
-            nalgebra::RowVector5::new(0., -1., 0., -PHI, -0.25 * (PHI + 1.)),
-            nalgebra::RowVector5::new(0., 0., PHI, 1., -0.25 * (PHI + 1.)),
-            nalgebra::RowVector5::new(0., 0., PHI, -1., -0.25 * (PHI + 1.)),
-            nalgebra::RowVector5::new(0., 0., -PHI, 1., -0.25 * (PHI + 1.)),
-            nalgebra::RowVector5::new(0., 0., -PHI, -1., -0.25 * (PHI + 1.)),
-        ];
-
-        Box::new(std::iter::once(foliage).chain(std::iter::once(trunk)))
+    fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+        Box::new(self.regions.iter().cloned())
     }
 }
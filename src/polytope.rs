@@ -0,0 +1,148 @@
+mod bounds;
+mod hull;
+mod regular;
+
+pub use bounds::bounding_box;
+
+use crate::render::{Renderable, Vertex};
+
+/// A convex 4D polytope, rendered as its triangulated 2-faces and occluding as a single region.
+pub struct Polytope {
+    vertices: Vec<nalgebra::Vector4<f32>>,
+    facets: Vec<nalgebra::RowVector5<f32>>,
+    /// The 2-faces, as lists of vertex indices in cyclic order.
+    faces: Vec<Vec<usize>>,
+}
+
+/// How far a vertex may be from a facet's hyperplane while still counting as lying on it,
+/// relative to the size of the polytope.
+const EPSILON: f32 = 1e-4;
+
+impl Polytope {
+    /// Build a polytope from its vertices and the outward normals of its facets.
+    /// The normals need not be normalized.
+    fn from_facet_normals(
+        vertices: Vec<nalgebra::Vector4<f32>>,
+        normals: impl IntoIterator<Item = nalgebra::Vector4<f32>>,
+    ) -> Self {
+        let facets = normals
+            .into_iter()
+            .map(|n| {
+                let n = n.normalize();
+                let d = vertices
+                    .iter()
+                    .map(|v| n.dot(v))
+                    .fold(f32::NEG_INFINITY, f32::max);
+                nalgebra::RowVector5::new(n[0], n[1], n[2], n[3], -d)
+            })
+            .collect();
+
+        Self::from_facets(vertices, facets)
+    }
+
+    /// Build a polytope from its vertices and facet half-spaces.
+    /// Each half-space `h` must satisfy `h * (p, 1) <= 0` for every vertex `p`,
+    /// with equality exactly for the vertices of that facet.
+    fn from_facets(
+        vertices: Vec<nalgebra::Vector4<f32>>,
+        facets: Vec<nalgebra::RowVector5<f32>>,
+    ) -> Self {
+        let scale = scale(&vertices);
+        let incidence: Vec<Vec<usize>> = facets
+            .iter()
+            .map(|h| {
+                (0..vertices.len())
+                    .filter(|&i| on_facet(h, &vertices[i], scale))
+                    .collect()
+            })
+            .collect();
+
+        // Two facets of a convex polytope meet in a face, which is a 2-face exactly when it has at least three vertices.
+        let mut faces = Vec::new();
+        for (i, a) in incidence.iter().enumerate() {
+            for b in &incidence[i + 1..] {
+                let shared: Vec<usize> = a.iter().copied().filter(|v| b.contains(v)).collect();
+                if shared.len() >= 3 {
+                    faces.push(cyclic_order(&vertices, shared));
+                }
+            }
+        }
+
+        Self {
+            vertices,
+            facets,
+            faces,
+        }
+    }
+}
+
+/// The largest coordinate of any of the points, or one if that is larger, for scaling tolerances.
+fn scale(points: &[nalgebra::Vector4<f32>]) -> f32 {
+    points.iter().map(|p| p.amax()).fold(1., f32::max)
+}
+
+/// Whether `v` lies on the hyperplane of `h`, for a polytope of the given `scale`.
+fn on_facet(h: &nalgebra::RowVector5<f32>, v: &nalgebra::Vector4<f32>, scale: f32) -> bool {
+    let normal = h.fixed_columns::<nalgebra::U4>(0).norm();
+    (h * v.fixed_resize::<nalgebra::U5, nalgebra::U1>(1.))[0].abs() < EPSILON * scale * normal
+}
+
+/// Sort the vertices of a planar convex polygon by angle around its centroid.
+fn cyclic_order(vertices: &[nalgebra::Vector4<f32>], mut face: Vec<usize>) -> Vec<usize> {
+    let centroid = face
+        .iter()
+        .fold(nalgebra::Vector4::zeros(), |acc, &i| acc + vertices[i])
+        / face.len() as f32;
+
+    let u = (vertices[face[0]] - centroid).normalize();
+    let v = face
+        .iter()
+        .map(|&i| {
+            let d = vertices[i] - centroid;
+            d - u * u.dot(&d)
+        })
+        .max_by(|a, b| a.norm().total_cmp(&b.norm()))
+        .unwrap()
+        .normalize();
+
+    let angle = |i: usize| {
+        let d = vertices[i] - centroid;
+        v.dot(&d).atan2(u.dot(&d))
+    };
+    face.sort_by(|&a, &b| angle(a).total_cmp(&angle(b)));
+    face
+}
+
+impl Renderable for Polytope {
+    fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+        // Each face is lit as part of the polytope's boundary, facing away from its center.
+        let center =
+            self.vertices.iter().sum::<nalgebra::Vector4<f32>>() / self.vertices.len() as f32;
+        Box::new(self.faces.iter().flat_map(move |face| {
+            let n = face.len();
+            let normal = crate::render::normal(
+                [
+                    self.vertices[face[0]],
+                    self.vertices[face[1]],
+                    self.vertices[face[2]],
+                ],
+                center,
+            );
+            let vertex = move |k: usize| {
+                let (s, c) = (k as f32 * 2. * core::f32::consts::PI / n as f32).sin_cos();
+                Vertex {
+                    pos: self.vertices[face[k]],
+                    texcoord: [0.5 + 0.5 * c, 0.5 + 0.5 * s],
+                    texture: None,
+                    color: [1.; 4],
+                    normal,
+                }
+            };
+            (1..n - 1).flat_map(move |k| vec![vertex(0), vertex(k), vertex(k + 1)].into_iter())
+        }))
+    }
+
+    fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+        Box::new(std::iter::once(self.facets.clone()))
+    }
+}
@@ -0,0 +1,176 @@
+//! Bounding boxes of regions, found by linear programming.
+
+/// How far a region's bounding box may extend in any direction. Unbounded regions are clipped to this.
+pub const LIMIT: f32 = 1e4;
+
+/// The axis-aligned bounding box of a region `{p : h * (p, 1) < 0 for all h}`,
+/// clipped to the cube of radius `LIMIT`, or `None` if the region is empty.
+/// A region with a coefficient which is not finite gets the whole cube.
+pub fn bounding_box(
+    region: &[nalgebra::RowVector5<f32>],
+) -> Option<(nalgebra::Vector4<f32>, nalgebra::Vector4<f32>)> {
+    if region.iter().flat_map(|h| h.iter()).any(|x| !x.is_finite()) {
+        return Some((
+            nalgebra::Vector4::repeat(-LIMIT),
+            nalgebra::Vector4::repeat(LIMIT),
+        ));
+    }
+    let mut min = nalgebra::Vector4::zeros();
+    let mut max = nalgebra::Vector4::zeros();
+    for axis in 0..4 {
+        let mut c = [0.; 4];
+        c[axis] = 1.;
+        max[axis] = maximize(region, c)? as f32;
+        c[axis] = -1.;
+        min[axis] = -maximize(region, c)? as f32;
+    }
+    Some((min, max))
+}
+
+/// Maximize `c * p` over the region, clipped to the cube of radius `LIMIT`.
+/// Returns `None` if the region is empty.
+///
+/// This solves the dual problem: minimize `b * y` subject to `A^T y = c` and `y >= 0`,
+/// where the primal constraints are `A p <= b`. The dual has only four equality constraints,
+/// and the cube's faces provide an obvious initial basis.
+fn maximize(region: &[nalgebra::RowVector5<f32>], c: [f64; 4]) -> Option<f64> {
+    let limit = f64::from(LIMIT);
+
+    // Columns of the tableau: first the region's half-spaces, then the cube's faces `p_i <= L` and `-p_i <= L`.
+    let mut columns: Vec<[f64; 4]> = region
+        .iter()
+        .map(|h| [h[0].into(), h[1].into(), h[2].into(), h[3].into()])
+        .collect();
+    let mut cost: Vec<f64> = region.iter().map(|h| -f64::from(h[4])).collect();
+    for sign in &[1., -1.] {
+        for axis in 0..4 {
+            let mut column = [0.; 4];
+            column[axis] = *sign;
+            columns.push(column);
+            cost.push(limit);
+        }
+    }
+
+    // Start with the basis of cube faces facing along `c`, negating rows so the right-hand side is nonnegative.
+    let m = region.len();
+    let mut basis = [0; 4];
+    let mut rhs = [0.; 4];
+    for row in 0..4 {
+        basis[row] = if c[row] >= 0. { m + row } else { m + 4 + row };
+        rhs[row] = c[row].abs();
+        if c[row] < 0. {
+            for column in &mut columns {
+                column[row] = -column[row];
+            }
+        }
+    }
+
+    const EPSILON: f64 = 1e-9;
+    for _ in 0..10_000 {
+        // Bland's rule: enter the first column with negative reduced cost.
+        let entering = (0..columns.len()).find(|&j| {
+            let reduced = cost[j] - (0..4).map(|i| cost[basis[i]] * columns[j][i]).sum::<f64>();
+            reduced < -EPSILON
+        });
+        let entering = match entering {
+            Some(j) => j,
+            None => return Some((0..4).map(|i| cost[basis[i]] * rhs[i]).sum()),
+        };
+
+        // If nothing limits the entering column, the dual is unbounded, so the region is empty.
+        let leaving = (0..4)
+            .filter(|&i| columns[entering][i] > EPSILON)
+            .min_by(|&i, &k| {
+                let a = rhs[i] / columns[entering][i];
+                let b = rhs[k] / columns[entering][k];
+                a.total_cmp(&b).then(basis[i].cmp(&basis[k]))
+            })?;
+
+        let pivot = columns[entering][leaving];
+        let factors: Vec<f64> = (0..4).map(|i| columns[entering][i] / pivot).collect();
+        for (i, &factor) in factors.iter().enumerate() {
+            if i != leaving {
+                rhs[i] -= factor * rhs[leaving];
+            }
+        }
+        rhs[leaving] /= pivot;
+        for column in &mut columns {
+            let x = column[leaving];
+            for (i, &factor) in factors.iter().enumerate() {
+                if i != leaving {
+                    column[i] -= factor * x;
+                }
+            }
+            column[leaving] = x / pivot;
+        }
+        basis[leaving] = entering;
+    }
+
+    // Bland's rule should not cycle, but rounding errors might make it.
+    // Rather than report a region as empty when it may not be, fall back to the whole cube.
+    Some(limit * c.iter().map(|x| x.abs()).sum::<f64>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polytope::Polytope;
+
+    #[test]
+    fn bounded_region() {
+        let region = Polytope::tesseract().facets().to_vec();
+        let (min, max) = bounding_box(&region).unwrap();
+        assert!(
+            (min + nalgebra::Vector4::repeat(0.5)).norm() < 1e-5,
+            "{}",
+            min
+        );
+        assert!(
+            (max - nalgebra::Vector4::repeat(0.5)).norm() < 1e-5,
+            "{}",
+            max
+        );
+
+        // A simplex, whose box is not given by any single half-space.
+        let simplex = vec![
+            nalgebra::RowVector5::new(-1., 0., 0., 0., 0.),
+            nalgebra::RowVector5::new(0., -1., 0., 0., 0.),
+            nalgebra::RowVector5::new(0., 0., -1., 0., 0.),
+            nalgebra::RowVector5::new(0., 0., 0., -1., 0.),
+            nalgebra::RowVector5::new(1., 1., 1., 1., -2.),
+        ];
+        let (min, max) = bounding_box(&simplex).unwrap();
+        assert!(min.norm() < 1e-5);
+        assert!((max - nalgebra::Vector4::repeat(2.)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn empty_region() {
+        let region = vec![
+            nalgebra::RowVector5::new(1., 0., 0., 0., 0.),
+            nalgebra::RowVector5::new(-1., 0., 0., 0., 1.),
+        ];
+        assert_eq!(bounding_box(&region), None);
+    }
+
+    #[test]
+    fn unbounded_region_is_clipped() {
+        let region = vec![nalgebra::RowVector5::new(0., 0., 1., 0., -2.)];
+        let (min, max) = bounding_box(&region).unwrap();
+        assert_eq!(min, nalgebra::Vector4::repeat(-LIMIT));
+        assert_eq!(max, nalgebra::Vector4::new(LIMIT, LIMIT, 2., LIMIT));
+    }
+
+    #[test]
+    fn non_finite_region_gets_the_whole_cube() {
+        let mut region = Polytope::tesseract().facets().to_vec();
+        region[0][2] = f32::NAN;
+        assert_eq!(
+            bounding_box(&region),
+            Some((
+                nalgebra::Vector4::repeat(-LIMIT),
+                nalgebra::Vector4::repeat(LIMIT)
+            ))
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! Incremental convex hull of a set of points in four dimensions.
+
+use super::{on_facet, Polytope};
+use std::collections::BTreeMap;
+
+type Vector = nalgebra::Vector4<f64>;
+
+/// A simplicial facet of the hull under construction.
+/// A point `p` lies outside the facet's hyperplane when `normal.dot(p) > offset`.
+struct Simplex {
+    vertices: [usize; 4],
+    normal: Vector,
+    offset: f64,
+}
+
+impl Simplex {
+    /// Orient the facet so that `interior` lies inside it.
+    fn new(points: &[Vector], vertices: [usize; 4], interior: &Vector) -> Self {
+        let [a, b, c, d] = vertices;
+        let mut normal = cross(
+            points[b] - points[a],
+            points[c] - points[a],
+            points[d] - points[a],
+        )
+        .normalize();
+        let mut offset = normal.dot(&points[a]);
+        if normal.dot(interior) > offset {
+            normal = -normal;
+            offset = -offset;
+        }
+        Self {
+            vertices,
+            normal,
+            offset,
+        }
+    }
+
+    fn distance(&self, p: &Vector) -> f64 {
+        self.normal.dot(p) - self.offset
+    }
+}
+
+/// The four-dimensional analogue of the cross product: a vector orthogonal to all three arguments.
+fn cross(u: Vector, v: Vector, w: Vector) -> Vector {
+    let m = nalgebra::Matrix3x4::from_rows(&[u.transpose(), v.transpose(), w.transpose()]);
+    Vector::from_fn(|i, _| {
+        let minor = m.remove_column(i).determinant();
+        if i % 2 == 0 {
+            minor
+        } else {
+            -minor
+        }
+    })
+}
+
+/// Find five affinely independent points, or `None` if the points all lie in a common hyperplane.
+fn initial_simplex(points: &[Vector], epsilon: f64) -> Option<[usize; 5]> {
+    let origin = *points.first()?;
+    let mut simplex = [0; 5];
+    let mut basis: Vec<Vector> = Vec::new();
+
+    for slot in simplex[1..].iter_mut() {
+        let residual = |p: &Vector| {
+            let mut r = p - origin;
+            for b in &basis {
+                r -= b * b.dot(&r);
+            }
+            r
+        };
+        let (i, r) = points
+            .iter()
+            .map(residual)
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.norm().total_cmp(&b.norm()))?;
+
+        if r.norm() < epsilon {
+            return None;
+        }
+        basis.push(r.normalize());
+        *slot = i;
+    }
+
+    Some(simplex)
+}
+
+impl Polytope {
+    /// The convex hull of a set of points.
+    /// Points which are not vertices of the hull are discarded.
+    /// Returns `None` if the points all lie in a common hyperplane, or any coordinate is not finite.
+    pub fn hull(points: &[nalgebra::Vector4<f32>]) -> Option<Self> {
+        if points.iter().flat_map(|p| p.iter()).any(|x| !x.is_finite()) {
+            return None;
+        }
+        let points: Vec<Vector> = points.iter().map(|p| p.map(f64::from)).collect();
+        let scale = points.iter().map(|p| p.amax()).fold(1., f64::max);
+        let epsilon = 1e-5 * scale;
+
+        let [a, b, c, d, e] = initial_simplex(&points, epsilon)?;
+        let interior = (points[a] + points[b] + points[c] + points[d] + points[e]) / 5.;
+
+        let mut simplices: Vec<Simplex> = vec![
+            [b, c, d, e],
+            [a, c, d, e],
+            [a, b, d, e],
+            [a, b, c, e],
+            [a, b, c, d],
+        ]
+        .into_iter()
+        .map(|vertices| Simplex::new(&points, vertices, &interior))
+        .collect();
+
+        for (p, point) in points.iter().enumerate() {
+            if [a, b, c, d, e].contains(&p) {
+                continue;
+            }
+
+            let (visible, hidden): (Vec<Simplex>, Vec<Simplex>) = simplices
+                .into_iter()
+                .partition(|s| s.distance(point) > epsilon);
+            simplices = hidden;
+
+            // The horizon consists of the ridges belonging to exactly one visible facet.
+            let mut ridges: BTreeMap<[usize; 3], usize> = BTreeMap::new();
+            for s in &visible {
+                let [a, b, c, d] = s.vertices;
+                for ridge in &mut [[b, c, d], [a, c, d], [a, b, d], [a, b, c]] {
+                    ridge.sort_unstable();
+                    *ridges.entry(*ridge).or_insert(0) += 1;
+                }
+            }
+
+            for ([a, b, c], count) in ridges {
+                if count == 1 {
+                    simplices.push(Simplex::new(&points, [a, b, c, p], &interior));
+                }
+            }
+        }
+
+        // Merge coplanar simplices into the true facets of the hull.
+        let mut planes: Vec<&Simplex> = Vec::new();
+        for s in &simplices {
+            if !planes.iter().any(|t| {
+                t.normal.dot(&s.normal) > 0.
+                    && s.vertices
+                        .iter()
+                        .all(|&i| t.distance(&points[i]).abs() < epsilon)
+            }) {
+                planes.push(s);
+            }
+        }
+        let facets: Vec<nalgebra::RowVector5<f32>> = planes
+            .iter()
+            .map(|s| {
+                let n = s.normal.map(|x| x as f32);
+                nalgebra::RowVector5::new(n[0], n[1], n[2], n[3], -s.offset as f32)
+            })
+            .collect();
+
+        let mut candidates: Vec<usize> =
+            simplices.iter().flat_map(|s| s.vertices.to_vec()).collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        // A vertex of a four-dimensional polytope lies on at least four facets.
+        // This discards points that ended up inside a facet, ridge or edge.
+        let vertices = candidates
+            .into_iter()
+            .map(|i| points[i].map(|x| x as f32))
+            .filter(|v| {
+                facets
+                    .iter()
+                    .filter(|h| on_facet(h, v, scale as f32))
+                    .count()
+                    >= 4
+            })
+            .collect();
+
+        Some(Self::from_facets(vertices, facets))
+    }
+
+    pub fn facets(&self) -> &[nalgebra::RowVector5<f32>] {
+        &self.facets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tesseract_vertices(size: f32) -> Vec<nalgebra::Vector4<f32>> {
+        (0..16)
+            .map(|i| nalgebra::Vector4::from_fn(|k, _| if i >> k & 1 == 1 { size } else { -size }))
+            .collect()
+    }
+
+    fn assert_tesseract(hull: &Polytope, size: f32) {
+        assert_eq!(hull.facets.len(), 8);
+        assert_eq!(hull.vertices.len(), 16);
+        for v in &hull.vertices {
+            assert!(v.iter().all(|x| x.abs() == size), "{} is not a vertex", v);
+        }
+    }
+
+    #[test]
+    fn interior_points_are_discarded() {
+        let mut points = tesseract_vertices(1.);
+        points.extend(vec![
+            nalgebra::Vector4::zeros(),
+            nalgebra::Vector4::new(0.5, -0.2, 0.9, 0.),
+            // On a facet and on an edge, but not a vertex.
+            nalgebra::Vector4::new(1., 0.3, -0.3, 0.),
+            nalgebra::Vector4::new(1., 1., 1., 0.5),
+        ]);
+        assert_tesseract(&Polytope::hull(&points).unwrap(), 1.);
+    }
+
+    #[test]
+    fn duplicate_points() {
+        let mut points = tesseract_vertices(1.);
+        points.extend(tesseract_vertices(1.));
+        points.push(points[3]);
+        assert_tesseract(&Polytope::hull(&points).unwrap(), 1.);
+    }
+
+    #[test]
+    fn large_coordinates() {
+        // Turned, so that rounding errors do not cancel out.
+        let rotation = nalgebra::Rotation2::new(0.3);
+        let points: Vec<_> = tesseract_vertices(1e4)
+            .into_iter()
+            .map(|v| {
+                let xy = rotation * nalgebra::Vector2::new(v[0], v[1]);
+                nalgebra::Vector4::new(xy[0], xy[1], v[2], v[3])
+            })
+            .collect();
+        let hull = Polytope::hull(&points).unwrap();
+        assert_eq!(hull.facets.len(), 8);
+        assert_eq!(hull.vertices.len(), 16);
+    }
+
+    #[test]
+    fn degenerate_points_have_no_hull() {
+        assert!(Polytope::hull(&[]).is_none());
+        // Four points always lie in a common hyperplane.
+        assert!(Polytope::hull(&tesseract_vertices(1.)[..4]).is_none());
+        let flat: Vec<_> = tesseract_vertices(1.)
+            .into_iter()
+            .map(|mut v| {
+                v[3] = 2.;
+                v
+            })
+            .collect();
+        assert!(Polytope::hull(&flat).is_none());
+        assert!(Polytope::hull(&[nalgebra::Vector4::repeat(1.); 10]).is_none());
+    }
+
+    #[test]
+    fn non_finite_points_have_no_hull() {
+        for &x in &[f32::NAN, f32::INFINITY] {
+            let mut points = tesseract_vertices(1.);
+            points[3][1] = x;
+            assert!(Polytope::hull(&points).is_none());
+        }
+    }
+}
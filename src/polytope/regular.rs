@@ -0,0 +1,198 @@
+//! The six convex regular polychora, each with circumradius one and centered at the origin.
+
+use super::Polytope;
+
+const PHI: f32 = 1.618_034;
+
+impl Polytope {
+    pub fn five_cell() -> Self {
+        let a = 1. / 5f32.sqrt();
+        let vertices: Vec<_> = vec![
+            [1., 1., 1., -a],
+            [1., -1., -1., -a],
+            [-1., 1., -1., -a],
+            [-1., -1., 1., -a],
+            [0., 0., 0., 4. * a],
+        ]
+        .into_iter()
+        .map(|v| nalgebra::Vector4::from(v).normalize())
+        .collect();
+
+        let normals: Vec<_> = vertices.iter().map(|v| -v).collect();
+        Self::from_facet_normals(vertices, normals)
+    }
+
+    pub fn tesseract() -> Self {
+        Self::from_facet_normals(
+            permutations([0.5, 0.5, 0.5, 0.5], false),
+            permutations([1., 0., 0., 0.], false),
+        )
+    }
+
+    pub fn sixteen_cell() -> Self {
+        Self::from_facet_normals(
+            permutations([1., 0., 0., 0.], false),
+            permutations([1., 1., 1., 1.], false),
+        )
+    }
+
+    pub fn twenty_four_cell() -> Self {
+        let mut normals = permutations([1., 0., 0., 0.], false);
+        normals.extend(permutations([1., 1., 1., 1.], false));
+
+        Self::from_facet_normals(scaled(permutations([1., 1., 0., 0.], false), 1.), normals)
+    }
+
+    pub fn hundred_twenty_cell() -> Self {
+        // The 120-cell is dual to the 600-cell, so its vertices lie in the directions of the 600-cell's facets.
+        let vertices = six_hundred_cell_vertices();
+        let cells = six_hundred_cell_cells(&vertices);
+        Self::from_facet_normals(scaled(cells, 1.), vertices)
+    }
+
+    pub fn six_hundred_cell() -> Self {
+        let vertices = six_hundred_cell_vertices();
+        let cells = six_hundred_cell_cells(&vertices);
+        Self::from_facet_normals(vertices, cells)
+    }
+}
+
+fn six_hundred_cell_vertices() -> Vec<nalgebra::Vector4<f32>> {
+    let mut out = permutations([0.5, 0.5, 0.5, 0.5], false);
+    out.extend(permutations([1., 0., 0., 0.], false));
+    out.extend(permutations([0.5 * PHI, 0.5, 0.5 * (PHI - 1.), 0.], true));
+    out
+}
+
+/// The centroids of the 600-cell's tetrahedral cells, which are exactly the sets of four mutually adjacent vertices.
+fn six_hundred_cell_cells(vertices: &[nalgebra::Vector4<f32>]) -> Vec<nalgebra::Vector4<f32>> {
+    // The edge length of a 600-cell with circumradius one is 1/phi.
+    let adjacent =
+        |a: usize, b: usize| ((vertices[a] - vertices[b]).norm() - (PHI - 1.)).abs() < 1e-3;
+
+    let mut out = Vec::new();
+    for a in 0..vertices.len() {
+        for b in a + 1..vertices.len() {
+            if !adjacent(a, b) {
+                continue;
+            }
+            for c in b + 1..vertices.len() {
+                if !adjacent(a, c) || !adjacent(b, c) {
+                    continue;
+                }
+                for d in c + 1..vertices.len() {
+                    if adjacent(a, d) && adjacent(b, d) && adjacent(c, d) {
+                        out.push((vertices[a] + vertices[b] + vertices[c] + vertices[d]) / 4.);
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Rescale every point to have the given norm.
+fn scaled(points: Vec<nalgebra::Vector4<f32>>, norm: f32) -> Vec<nalgebra::Vector4<f32>> {
+    points.into_iter().map(|p| p.normalize() * norm).collect()
+}
+
+/// All distinct points obtained from `v` by permuting its coordinates (only evenly, if `even` is set)
+/// and changing the signs of its nonzero coordinates.
+fn permutations(v: [f32; 4], even: bool) -> Vec<nalgebra::Vector4<f32>> {
+    let mut out: Vec<nalgebra::Vector4<f32>> = Vec::new();
+
+    for a in 0..4 {
+        for b in 0..4 {
+            for c in 0..4 {
+                if a == b || a == c || b == c {
+                    continue;
+                }
+                let d = 6 - a - b - c;
+                let p = [a, b, c, d];
+
+                let inversions = (0..4)
+                    .flat_map(|i| (i + 1..4).map(move |j| (i, j)))
+                    .filter(|&(i, j)| p[i] > p[j])
+                    .count();
+                if even && inversions % 2 == 1 {
+                    continue;
+                }
+
+                for signs in 0..16 {
+                    let mut x = nalgebra::Vector4::zeros();
+                    for i in 0..4 {
+                        x[i] = if signs & (1 << i) == 0 {
+                            v[p[i]]
+                        } else {
+                            -v[p[i]]
+                        };
+                    }
+                    if !out.contains(&x) {
+                        out.push(x);
+                    }
+                }
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check the numbers of vertices, 2-faces and facets, and that every vertex is on the unit sphere.
+    fn check(polytope: Polytope, vertices: usize, faces: usize, facets: usize) {
+        assert_eq!(polytope.vertices.len(), vertices);
+        assert_eq!(polytope.faces.len(), faces);
+        assert_eq!(polytope.facets.len(), facets);
+        for v in &polytope.vertices {
+            assert!(
+                (v.norm() - 1.).abs() < 1e-5,
+                "{} is not on the unit sphere",
+                v
+            );
+        }
+        // Each facet touches the polytope, and every vertex is on or inside it.
+        for h in &polytope.facets {
+            let distances: Vec<f32> = polytope
+                .vertices
+                .iter()
+                .map(|v| (h * v.insert_row(4, 1.))[0])
+                .collect();
+            assert!(distances.iter().all(|&d| d < 1e-4));
+            assert!(distances.iter().any(|&d| d.abs() < 1e-4));
+        }
+    }
+
+    #[test]
+    fn five_cell() {
+        check(Polytope::five_cell(), 5, 10, 5);
+    }
+
+    #[test]
+    fn tesseract() {
+        check(Polytope::tesseract(), 16, 24, 8);
+    }
+
+    #[test]
+    fn sixteen_cell() {
+        check(Polytope::sixteen_cell(), 8, 32, 16);
+    }
+
+    #[test]
+    fn twenty_four_cell() {
+        check(Polytope::twenty_four_cell(), 24, 96, 24);
+    }
+
+    #[test]
+    fn hundred_twenty_cell() {
+        check(Polytope::hundred_twenty_cell(), 600, 720, 120);
+    }
+
+    #[test]
+    fn six_hundred_cell() {
+        check(Polytope::six_hundred_cell(), 120, 1200, 600);
+    }
+}
@@ -2,10 +2,18 @@ mod program;
 mod shader;
 
 mod from_tex;
+#[cfg(test)]
+pub mod software;
 mod to_tex;
 
+mod bvh;
+mod packed;
 mod renderable;
-pub use renderable::{Renderable, Transform};
+mod scene;
+mod texture;
+pub use renderable::{normal, Renderable, Retexture, Textured, Tinted, Transform};
+pub use scene::{ObjectId, Scene};
+pub use texture::Texture;
 
 use std::rc::Rc;
 pub use to_tex::{RenderFunction, Uniforms, Vertex};
@@ -14,10 +22,7 @@ use wasm_bindgen::JsCast;
 
 type GL = web_sys::WebGl2RenderingContext;
 
-pub fn make_fn(
-    canvas: &web_sys::HtmlCanvasElement,
-    renderable: impl Renderable,
-) -> Result<Box<RenderFunction>, JsValue> {
+pub fn make_fn(canvas: &web_sys::HtmlCanvasElement) -> Result<Box<RenderFunction>, JsValue> {
     let gl = canvas
         .get_context("webgl2")?
         .ok_or("\"webgl2\" context identifier not supported.")?
@@ -32,31 +37,70 @@ pub fn make_fn(
     let tex = gl.create_texture().ok_or("create_texture failed.")?;
     gl.bind_texture(GL::TEXTURE_2D, Some(&tex));
     gl.pixel_storei(GL::UNPACK_ALIGNMENT, 1);
-
-    gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
-        GL::TEXTURE_2D,
-        0,                  // level
-        GL::RGBA32F as i32, // internal_format
-        1600,               // width
-        800,                // height
-        0,                  // border
-        GL::RGBA,           // format
-        GL::FLOAT,          // type
-        None,
-    )?;
-
     gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
     gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
     gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
     gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+    // The texture is allocated on the first frame, once the canvas's size is known.
+    let mut tex_size = [0, 0];
 
     let gl = Rc::new(gl);
-    let to_tex = to_tex::make_fn(Rc::clone(&gl), &tex, renderable)?;
-    let from_tex = from_tex::make_fn(gl)?;
+    let mut to_tex = to_tex::make_fn(Rc::clone(&gl), &tex)?;
+    let from_tex = from_tex::make_fn(Rc::clone(&gl))?;
 
-    Ok(Box::new(move |uniforms| {
-        to_tex(uniforms)?;
-        from_tex(&tex);
+    Ok(Box::new(move |scene, uniforms| {
+        let size = [gl.drawing_buffer_width(), gl.drawing_buffer_height()];
+        if size != tex_size {
+            gl.bind_texture(GL::TEXTURE_2D, Some(&tex));
+            gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                GL::TEXTURE_2D,
+                0,                  // level
+                GL::RGBA32F as i32, // internal_format
+                size[0],            // width
+                size[1],            // height
+                0,                  // border
+                GL::RGBA,           // format
+                GL::FLOAT,          // type
+                None,
+            )?;
+            tex_size = size;
+        }
+
+        to_tex(scene, uniforms, size)?;
+        from_tex(&tex, size);
         Ok(())
     }))
 }
+
+/// The viewports of the two three-cameras on a canvas of the given size, as `[x, y, width, height]`.
+///
+/// Each is the largest square fitting in its half of the canvas, and the two meet at the canvas's center.
+pub(crate) fn viewports([width, height]: [i32; 2]) -> [[i32; 4]; 2] {
+    let size = (width / 2).min(height);
+    let bottom = (height - size) / 2;
+    [
+        [width / 2 - size, bottom, size, size],
+        [width / 2, bottom, size, size],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewports_are_centered_squares() {
+        assert_eq!(
+            viewports([1600, 800]),
+            [[0, 0, 800, 800], [800, 0, 800, 800]]
+        );
+        assert_eq!(
+            viewports([2000, 600]),
+            [[400, 0, 600, 600], [1000, 0, 600, 600]]
+        );
+        assert_eq!(
+            viewports([1000, 900]),
+            [[0, 200, 500, 500], [500, 200, 500, 500]]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! A bounding volume hierarchy over the scene's regions,
+//! letting the fragment shader skip regions far from the line of sight.
+
+/// A region, given by its range of half-spaces in the region texture, and its bounding box.
+pub struct Leaf {
+    pub first: usize,
+    pub count: usize,
+    pub min: nalgebra::Vector4<f32>,
+    pub max: nalgebra::Vector4<f32>,
+}
+
+/// A node of the hierarchy. Nodes are stored in depth-first order,
+/// so a node's first child immediately follows it, and `escape` is the node following its subtree.
+/// Leaves have `count > 0`; internal nodes have `count == 0`.
+struct Node {
+    min: nalgebra::Vector4<f32>,
+    max: nalgebra::Vector4<f32>,
+    escape: usize,
+    first: usize,
+    count: usize,
+}
+
+/// Each node is stored as three texels, `min`, `max`, and `(escape, first, count, 0)`.
+pub const FLOATS_PER_NODE: usize = 12;
+
+pub struct Bvh {
+    nodes: Vec<Node>,
+}
+
+impl Bvh {
+    pub fn new(mut leaves: Vec<Leaf>) -> Self {
+        let mut nodes = Vec::with_capacity(2 * leaves.len());
+        if !leaves.is_empty() {
+            build(&mut leaves, &mut nodes);
+        }
+        Self { nodes }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn data(&self) -> Vec<f32> {
+        let mut out = Vec::with_capacity(self.nodes.len() * FLOATS_PER_NODE);
+        for node in &self.nodes {
+            out.extend(node.min.iter());
+            out.extend(node.max.iter());
+            out.extend_from_slice(&[node.escape as f32, node.first as f32, node.count as f32, 0.]);
+        }
+        out
+    }
+
+    /// Whether `hit_leaf(first, count)` holds for some leaf such that `hit_box(min, max)` holds
+    /// for its box and those of all its ancestors.
+    /// The nodes are visited in the same order as in the fragment shader's traversal.
+    #[cfg(test)]
+    pub fn any(
+        &self,
+        mut hit_box: impl FnMut(&nalgebra::Vector4<f32>, &nalgebra::Vector4<f32>) -> bool,
+        mut hit_leaf: impl FnMut(usize, usize) -> bool,
+    ) -> bool {
+        let mut index = 0;
+        while let Some(node) = self.nodes.get(index) {
+            if hit_box(&node.min, &node.max) {
+                if node.count > 0 && hit_leaf(node.first, node.count) {
+                    return true;
+                }
+                index += 1;
+            } else {
+                index = node.escape;
+            }
+        }
+        false
+    }
+}
+
+fn build(leaves: &mut [Leaf], nodes: &mut Vec<Node>) {
+    let index = nodes.len();
+
+    let mut min = leaves[0].min;
+    let mut max = leaves[0].max;
+    for leaf in leaves.iter() {
+        min = min.zip_map(&leaf.min, f32::min);
+        max = max.zip_map(&leaf.max, f32::max);
+    }
+
+    if let [leaf] = leaves {
+        nodes.push(Node {
+            min,
+            max,
+            escape: index + 1,
+            first: leaf.first,
+            count: leaf.count,
+        });
+        return;
+    }
+
+    nodes.push(Node {
+        min,
+        max,
+        escape: 0,
+        first: 0,
+        count: 0,
+    });
+
+    // Split at the median along the axis in which the leaves' centers are most spread out.
+    let center = |leaf: &Leaf| (leaf.min + leaf.max) / 2.;
+    let mut low = center(&leaves[0]);
+    let mut high = low;
+    for leaf in leaves.iter() {
+        low = low.zip_map(&center(leaf), f32::min);
+        high = high.zip_map(&center(leaf), f32::max);
+    }
+    let axis = (high - low).imax();
+    leaves.sort_by(|a, b| center(a)[axis].total_cmp(&center(b)[axis]));
+
+    let (left, right) = leaves.split_at_mut(leaves.len() / 2);
+    build(left, nodes);
+    build(right, nodes);
+
+    nodes[index].escape = nodes.len();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unit boxes with their lowest corners at `(i, 0, 0, 0)`, numbered by `first`.
+    fn leaves(n: usize) -> Vec<Leaf> {
+        (0..n)
+            .map(|i| Leaf {
+                first: i,
+                count: 1,
+                min: nalgebra::Vector4::new(i as f32, 0., 0., 0.),
+                max: nalgebra::Vector4::new(i as f32 + 1., 1., 1., 1.),
+            })
+            .collect()
+    }
+
+    /// The leaves whose boxes contain `p`, in the order they are visited.
+    fn containing(bvh: &Bvh, p: nalgebra::Vector4<f32>) -> Vec<usize> {
+        let mut out = Vec::new();
+        bvh.any(
+            |min, max| (0..4).all(|i| min[i] <= p[i] && p[i] <= max[i]),
+            |first, _| {
+                out.push(first);
+                false
+            },
+        );
+        out
+    }
+
+    #[test]
+    fn every_leaf_is_visited_once() {
+        let bvh = Bvh::new(leaves(7));
+        assert_eq!(bvh.len(), 13);
+        assert_eq!(bvh.data().len(), 13 * FLOATS_PER_NODE);
+
+        let mut visited = Vec::new();
+        bvh.any(
+            |_, _| true,
+            |first, count| {
+                assert_eq!(count, 1);
+                visited.push(first);
+                false
+            },
+        );
+        visited.sort_unstable();
+        assert_eq!(visited, (0..7).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn traversal_skips_boxes_that_miss() {
+        let bvh = Bvh::new(leaves(9));
+        assert_eq!(
+            containing(&bvh, nalgebra::Vector4::new(4.5, 0.5, 0.5, 0.5)),
+            vec![4]
+        );
+        assert_eq!(
+            containing(&bvh, nalgebra::Vector4::new(3., 0.5, 0.5, 0.5)),
+            vec![2, 3]
+        );
+        assert!(containing(&bvh, nalgebra::Vector4::new(4.5, 2., 0.5, 0.5)).is_empty());
+
+        // Traversal stops at the first hit.
+        let mut calls = 0;
+        assert!(bvh.any(
+            |_, _| true,
+            |_, _| {
+                calls += 1;
+                true
+            }
+        ));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn boxes_with_nan_are_still_visited() {
+        let mut leaves = leaves(5);
+        leaves[2].min[0] = f32::NAN;
+        let bvh = Bvh::new(leaves);
+        let mut visited = Vec::new();
+        bvh.any(
+            |_, _| true,
+            |first, _| {
+                visited.push(first);
+                false
+            },
+        );
+        visited.sort_unstable();
+        assert_eq!(visited, (0..5).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn empty_hierarchy() {
+        let bvh = Bvh::new(Vec::new());
+        assert_eq!(bvh.len(), 0);
+        assert!(!bvh.any(|_, _| true, |_, _| true));
+    }
+}
@@ -32,7 +32,7 @@ void main() {
 
 "#;
 
-pub fn make_fn(gl: Rc<GL>) -> Result<impl 'static + Fn(&web_sys::WebGlTexture), JsValue> {
+pub fn make_fn(gl: Rc<GL>) -> Result<impl 'static + Fn(&web_sys::WebGlTexture, [i32; 2]), JsValue> {
     let program = Program::new(Rc::clone(&gl), VERTEX_SHADER, FRAGMENT_SHADER)?;
 
     let coord_loc = program.attribute("coord")?;
@@ -53,11 +53,11 @@ pub fn make_fn(gl: Rc<GL>) -> Result<impl 'static + Fn(&web_sys::WebGlTexture),
         GL::STATIC_DRAW,
     );
 
-    Ok(move |tex: &web_sys::WebGlTexture| {
+    Ok(move |tex: &web_sys::WebGlTexture, size: [i32; 2]| {
         gl.bind_framebuffer(GL::FRAMEBUFFER, None);
         gl.bind_vertex_array(Some(&vao));
 
-        gl.viewport(0, 0, 1600, 800);
+        gl.viewport(0, 0, size[0], size[1]);
         gl.clear_color(0., 0., 0., 1.);
         gl.clear(GL::COLOR_BUFFER_BIT);
 
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// A CPU-side copy of a GPU array, in which each object of the scene owns a contiguous range of elements.
+/// Unowned elements are filled with a blank element, chosen to have no effect on the rendered image.
+pub struct Packed {
+    data: Vec<f32>,
+    blank: Vec<f32>,
+    ranges: BTreeMap<usize, Range<usize>>,
+    /// Unowned ranges, sorted and non-adjacent, all ending before the end of `data`.
+    free: Vec<Range<usize>>,
+    /// Ranges which have changed since they were last uploaded.
+    changed: Vec<Range<usize>>,
+}
+
+impl Packed {
+    /// The number of floats per element is the length of `blank`.
+    pub fn new(blank: Vec<f32>) -> Self {
+        Self {
+            data: Vec::new(),
+            blank,
+            ranges: BTreeMap::new(),
+            free: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+
+    pub fn stride(&self) -> usize {
+        self.blank.len()
+    }
+
+    /// The number of elements, including unowned ones.
+    pub fn len(&self) -> usize {
+        self.data.len() / self.stride()
+    }
+
+    pub fn data(&self, range: Range<usize>) -> &[f32] {
+        &self.data[range.start * self.stride()..range.end * self.stride()]
+    }
+
+    /// The range of elements belonging to the object at `index`.
+    pub fn range(&self, index: usize) -> Option<Range<usize>> {
+        self.ranges.get(&index).cloned()
+    }
+
+    /// Replace the elements belonging to the object at `index`.
+    pub fn set(&mut self, index: usize, data: &[f32]) {
+        let stride = self.stride();
+
+        if let Some(old) = self.ranges.remove(&index) {
+            for chunk in self.data[old.start * stride..old.end * stride].chunks_mut(stride) {
+                chunk.copy_from_slice(&self.blank);
+            }
+            self.changed.push(old.clone());
+            self.release(old);
+        }
+
+        if data.is_empty() {
+            return;
+        }
+
+        let range = self.allocate(data.len() / stride);
+        self.data[range.start * stride..range.end * stride].copy_from_slice(data);
+        self.changed.push(range.clone());
+        self.ranges.insert(index, range);
+    }
+
+    /// The ranges changed since this was last called, excluding those since dropped from the end.
+    pub fn take_changed(&mut self) -> Vec<Range<usize>> {
+        let len = self.len();
+        self.changed
+            .drain(..)
+            .map(|r| r.start..r.end.min(len))
+            .filter(|r| r.start < r.end)
+            .collect()
+    }
+
+    fn allocate(&mut self, n: usize) -> Range<usize> {
+        if let Some(i) = self.free.iter().position(|r| r.len() >= n) {
+            let r = self.free.remove(i);
+            if r.len() > n {
+                self.free.insert(i, r.start + n..r.end);
+            }
+            r.start..r.start + n
+        } else {
+            let start = self.len();
+            for _ in 0..n {
+                self.data.extend_from_slice(&self.blank);
+            }
+            start..start + n
+        }
+    }
+
+    fn release(&mut self, range: Range<usize>) {
+        let i = self
+            .free
+            .iter()
+            .position(|r| r.start > range.start)
+            .unwrap_or(self.free.len());
+        self.free.insert(i, range);
+
+        // Merge with the neighbouring ranges, if adjacent.
+        if i + 1 < self.free.len() && self.free[i].end == self.free[i + 1].start {
+            self.free[i].end = self.free.remove(i + 1).end;
+        }
+        if i > 0 && self.free[i - 1].end == self.free[i].start {
+            self.free[i - 1].end = self.free.remove(i).end;
+        }
+
+        // Free space at the end can simply be dropped.
+        if let Some(last) = self.free.last() {
+            if last.end == self.len() {
+                self.data.truncate(last.start * self.stride());
+                self.free.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An object of `n` elements, each filled with `value`.
+    fn object(n: usize, value: f32) -> Vec<f32> {
+        vec![value; 2 * n]
+    }
+
+    fn packed() -> Packed {
+        let mut packed = Packed::new(vec![0., -1.]);
+        packed.set(0, &object(2, 1.));
+        packed.set(1, &object(3, 2.));
+        packed.set(2, &object(1, 3.));
+        packed
+    }
+
+    #[test]
+    fn freed_space_is_reused() {
+        let mut packed = packed();
+        assert_eq!(packed.len(), 6);
+        assert_eq!(packed.take_changed(), vec![0..2, 2..5, 5..6]);
+
+        packed.set(0, &[]);
+        assert_eq!(packed.range(0), None);
+        assert_eq!(packed.data(0..2), &[0., -1., 0., -1.]);
+        assert_eq!(packed.take_changed(), vec![0..2]);
+
+        packed.set(3, &object(1, 4.));
+        assert_eq!(packed.range(3), Some(0..1));
+        // Too big for what is left of the gap, so it goes at the end.
+        packed.set(4, &object(2, 5.));
+        assert_eq!(packed.range(4), Some(6..8));
+        assert_eq!(packed.data(0..8)[..2], [4., 4.]);
+        assert_eq!(packed.len(), 8);
+    }
+
+    #[test]
+    fn neighbouring_free_blocks_merge() {
+        let mut packed = packed();
+        packed.set(0, &[]);
+        packed.set(1, &[]);
+        assert_eq!(packed.len(), 6);
+
+        packed.set(3, &object(5, 4.));
+        assert_eq!(packed.range(3), Some(0..5));
+        assert_eq!(packed.len(), 6);
+    }
+
+    #[test]
+    fn free_space_at_the_end_is_dropped() {
+        let mut packed = packed();
+        packed.take_changed();
+        packed.set(1, &[]);
+        packed.set(2, &[]);
+        assert_eq!(packed.len(), 2);
+        // The dropped elements are not reported, since there is nothing left to upload there.
+        assert_eq!(packed.take_changed(), vec![]);
+
+        // Replacing the only object drops all of its old space first, so the bigger one starts at the beginning.
+        packed.set(0, &object(4, 6.));
+        assert_eq!(packed.range(0), Some(0..4));
+        assert_eq!(packed.len(), 4);
+        assert_eq!(packed.data(0..4), &object(4, 6.)[..]);
+    }
+}
@@ -1,119 +1,242 @@
-use super::Vertex;
+use super::{Texture, Vertex};
+use std::rc::Rc;
 
 pub trait Renderable {
     fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>>;
     fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>>;
-    fn fragment_shader(&self) -> String {
-        let mut out = String::new();
+}
 
-        out += r"#version 300 es
+pub struct Transform<R> {
+    inner: R,
+    transform: nalgebra::Matrix5<f32>,
+    transform_inv: nalgebra::Matrix5<f32>,
+}
 
-precision mediump float;
+impl<R> Transform<R> {
+    /// Apply an arbitrary invertible 5x5 homogeneous matrix to `inner`.
+    /// Returns `None` if the matrix is singular.
+    pub fn new(inner: R, transform: nalgebra::Matrix5<f32>) -> Option<Self> {
+        Some(Self {
+            inner,
+            transform,
+            transform_inv: transform.try_inverse()?,
+        })
+    }
+
+    pub fn identity(inner: R) -> Self {
+        Self {
+            inner,
+            transform: nalgebra::Matrix5::identity(),
+            transform_inv: nalgebra::Matrix5::identity(),
+        }
+    }
 
-in vec4 vpos;
-in vec2 vtexcoord;
-in vec4 vdata;
+    /// Scale each axis by the corresponding component of `factors`.
+    /// Returns `None` if any of them is zero.
+    pub fn nonuniform_scaling(inner: R, factors: nalgebra::Vector4<f32>) -> Option<Self> {
+        let (transform, transform_inv) = scaling_matrices(factors)?;
+        Some(Self {
+            inner,
+            transform,
+            transform_inv,
+        })
+    }
 
-out vec4 color;
+    /// Follow this transform with a translation.
+    pub fn translate(self, vector: nalgebra::Vector4<f32>) -> Self {
+        let t = nalgebra::Translation { vector };
+        self.then(t.to_homogeneous(), t.inverse().to_homogeneous())
+    }
 
-uniform vec4 four_camera_pos;
-uniform sampler2D tex;
-uniform vec3 three_screen_size;
+    /// Follow this transform with a rotation by `angle` radians in the plane spanned by coordinate axes
+    /// `plane.0` and `plane.1`, taking the first axis towards the second.
+    /// Returns `None` unless the axes are two different ones of the four.
+    pub fn rotate(self, plane: (usize, usize), angle: f32) -> Option<Self> {
+        let (transform, transform_inv) = rotation_matrices(plane, angle)?;
+        Some(self.then(transform, transform_inv))
+    }
 
-vec2 clip(vec2 minmax, vec4 pos, vec4 target, vec4 abcd, float e) {
-    float x = dot(abcd, pos) + e;
-    float y = dot(abcd, target) + e;
+    /// Follow this transform with a scaling, as in [`Transform::nonuniform_scaling`].
+    pub fn scale(self, factors: nalgebra::Vector4<f32>) -> Option<Self> {
+        let (transform, transform_inv) = scaling_matrices(factors)?;
+        Some(self.then(transform, transform_inv))
+    }
 
-    if (x > y) {
-        minmax.x = max(minmax.x, x/(x-y));
-    } else {
-        minmax.y = min(minmax.y, x/(x-y));
+    pub fn into_inner(self) -> R {
+        self.inner
     }
 
-    return minmax;
+    /// Replace the transformation matrix, keeping the same inner object.
+    /// Returns `None`, leaving the transform unchanged, if the matrix is singular.
+    pub fn set_matrix(&mut self, transform: nalgebra::Matrix5<f32>) -> Option<()> {
+        self.transform_inv = transform.try_inverse()?;
+        self.transform = transform;
+        Some(())
+    }
+
+    fn then(
+        self,
+        transform: nalgebra::Matrix5<f32>,
+        transform_inv: nalgebra::Matrix5<f32>,
+    ) -> Self {
+        Self {
+            inner: self.inner,
+            transform: transform * self.transform,
+            transform_inv: self.transform_inv * transform_inv,
+        }
+    }
 }
 
-bool intersects_scene(vec4 pos, vec4 target) {
-    vec2 minmax;
-";
+fn rotation_matrices(
+    (i, j): (usize, usize),
+    angle: f32,
+) -> Option<(nalgebra::Matrix5<f32>, nalgebra::Matrix5<f32>)> {
+    if i >= 4 || j >= 4 || i == j {
+        return None;
+    }
 
-        for r in self.regions() {
-            out += "    minmax = vec2(0., 0.999);\n";
+    let (s, c) = angle.sin_cos();
+    let mut m = nalgebra::Matrix5::identity();
+    m[(i, i)] = c;
+    m[(i, j)] = -s;
+    m[(j, i)] = s;
+    m[(j, j)] = c;
 
-            for h in r {
-                out += &format!(
-                    "    minmax = clip(minmax, pos, target, vec4({:.9}, {:.9}, {:.9}, {:.9}), {:.9});\n",
-                    h[0], h[1], h[2], h[3], h[4]
-                )
-            }
+    Some((m, m.transpose()))
+}
 
-            out += r"
-    if (minmax.y > minmax.x) {
-        return true;
+fn scaling_matrices(
+    factors: nalgebra::Vector4<f32>,
+) -> Option<(nalgebra::Matrix5<f32>, nalgebra::Matrix5<f32>)> {
+    if factors.iter().any(|&x| x == 0.) {
+        return None;
     }
-"
-        }
 
-        out += "
-    return false;
+    Some((
+        nalgebra::Matrix5::from_diagonal(&factors.fixed_resize(1.)),
+        nalgebra::Matrix5::from_diagonal(&factors.map(|x| 1. / x).fixed_resize(1.)),
+    ))
 }
 
-void main() {
+impl<R: Renderable> Renderable for Transform<R> {
+    fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+        Box::new(self.inner.triangles().map(move |mut v| {
+            if v.normal != nalgebra::Vector4::zeros() {
+                // Transform the hyperplane through the vertex orthogonal to the normal, as regions are transformed.
+                let h = v.normal.transpose().insert_column(4, -v.normal.dot(&v.pos))
+                    * self.transform_inv;
+                v.normal = h.fixed_columns::<nalgebra::U4>(0).transpose().normalize();
+            }
 
-    vec3 data = vdata.xyz / vdata.w;
+            let old_pos: nalgebra::Vector5<f32> = v.pos.fixed_resize(1.);
+            let new_pos = self.transform * old_pos;
+            v.pos = new_pos.fixed_rows::<nalgebra::U4>(0) / new_pos[4];
+            v
+        }))
+    }
 
-    if (abs(data.x) > three_screen_size.x || abs(data.y) > three_screen_size.y || abs(data.z) > three_screen_size.z || abs(vdata.w) < 0.) {
-        // Outside three-screen, so invisible.
-        color = vec4(0.);
-    } else if (intersects_scene(four_camera_pos, vpos)) {
-        // Occluded, so invisible.
-        color = vec4(0.);
-    } else {
-        color = texture(tex, vtexcoord) / 5.0;
+    fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+        Box::new(self.inner.regions().map(move |mut r| {
+            for h in r.iter_mut() {
+                *h *= self.transform_inv;
+            }
+            r
+        }))
     }
 }
 
-";
-
-        out
+/// The normal of the triangle `a b c` relative to a cell containing it, for lighting.
+///
+/// This is the unit vector orthogonal to the triangle pointing most directly away from `inside`,
+/// a point inside the cell. It is zero if the triangle is degenerate or `inside` lies in its plane.
+pub fn normal(
+    [a, b, c]: [nalgebra::Vector4<f32>; 3],
+    inside: nalgebra::Vector4<f32>,
+) -> nalgebra::Vector4<f32> {
+    // Remove the components along an orthonormal basis of the triangle's plane, by Gram-Schmidt.
+    let mut out = (a + b + c) / 3. - inside;
+    let mut basis: Vec<nalgebra::Vector4<f32>> = Vec::new();
+    for edge in &[b - a, c - a] {
+        let mut edge = *edge;
+        for e in &basis {
+            edge -= e * e.dot(&edge);
+        }
+        if edge.norm() < 1e-6 {
+            return nalgebra::Vector4::zeros();
+        }
+        let edge = edge.normalize();
+        out -= edge * edge.dot(&out);
+        basis.push(edge);
+    }
+    if out.norm() < 1e-6 {
+        nalgebra::Vector4::zeros()
+    } else {
+        out.normalize()
     }
 }
 
-pub struct Transform<R> {
-    inner: R,
-    transform: nalgebra::Matrix5<f32>,
-    transform_inv: nalgebra::Matrix5<f32>,
+/// Maps the texture coordinates of `inner` from the unit square into the rectangle from `min` to `max`.
+///
+/// A rectangle of zero size paints the whole object with the color of a single texel.
+pub struct Retexture<R> {
+    pub inner: R,
+    pub min: [f32; 2],
+    pub max: [f32; 2],
 }
 
-impl<R> Transform<R> {
-    pub fn translation(inner: R, vector: nalgebra::Vector4<f32>) -> Self {
-        let t = nalgebra::Translation { vector };
-        Self {
-            inner,
-            transform: t.to_homogeneous(),
-            transform_inv: t.inverse().to_homogeneous(),
-        }
+impl<R: Renderable> Renderable for Retexture<R> {
+    fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+        Box::new(self.inner.triangles().map(move |mut v| {
+            for i in 0..2 {
+                v.texcoord[i] = self.min[i] + (self.max[i] - self.min[i]) * v.texcoord[i];
+            }
+            v
+        }))
+    }
+
+    fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+        self.inner.regions()
     }
 }
 
-impl<R: Renderable> Renderable for Transform<R> {
+/// Paints `inner` with `texture`, except for those parts of it which already have a texture of their own.
+pub struct Textured<R> {
+    pub inner: R,
+    pub texture: Rc<Texture>,
+}
+
+impl<R: Renderable> Renderable for Textured<R> {
     fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
         Box::new(self.inner.triangles().map(move |mut v| {
-            let old_pos: nalgebra::Vector5<f32> = v.pos.fixed_resize(1.);
-            let new_pos = self.transform * old_pos;
-            v.pos = new_pos.fixed_rows::<nalgebra::U4>(0) / new_pos[4];
+            v.texture = v.texture.or_else(|| Some(Rc::clone(&self.texture)));
             v
         }))
     }
 
     fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
-        Box::new(self.inner.regions().map(move |mut r| {
-            for h in r.iter_mut() {
-                *h *= self.transform_inv;
+        self.inner.regions()
+    }
+}
+
+/// Multiplies the colors of `inner` by `color`, whose alpha channel makes the object more or less opaque.
+pub struct Tinted<R> {
+    pub inner: R,
+    pub color: [f32; 4],
+}
+
+impl<R: Renderable> Renderable for Tinted<R> {
+    fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+        Box::new(self.inner.triangles().map(move |mut v| {
+            for (c, t) in v.color.iter_mut().zip(&self.color) {
+                *c *= t;
             }
-            r
+            v
         }))
     }
+
+    fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+        self.inner.regions()
+    }
 }
 
 impl<R1: Renderable, R2: Renderable> Renderable for (R1, R2) {
@@ -125,3 +248,256 @@ impl<R1: Renderable, R2: Renderable> Renderable for (R1, R2) {
         Box::new(self.0.regions().chain(self.1.regions()))
     }
 }
+
+impl<R: Renderable> Renderable for [R] {
+    fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+        Box::new(self.iter().flat_map(|r| r.triangles()))
+    }
+
+    fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+        Box::new(self.iter().flat_map(|r| r.regions()))
+    }
+}
+
+impl<R: Renderable, const N: usize> Renderable for [R; N] {
+    fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+        self[..].triangles()
+    }
+
+    fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+        self[..].regions()
+    }
+}
+
+impl<R: Renderable> Renderable for Vec<R> {
+    fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+        self[..].triangles()
+    }
+
+    fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+        self[..].regions()
+    }
+}
+
+impl<R: Renderable> Renderable for Option<R> {
+    fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+        Box::new(self.iter().flat_map(|r| r.triangles()))
+    }
+
+    fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+        Box::new(self.iter().flat_map(|r| r.regions()))
+    }
+}
+
+impl<R: Renderable + ?Sized> Renderable for Box<R> {
+    fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+        (**self).triangles()
+    }
+
+    fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+        (**self).regions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polytope::Polytope;
+    use core::f32::consts::PI;
+
+    /// The cube from -1 to 1 on every axis, as one region.
+    struct Cube;
+
+    impl Renderable for Cube {
+        fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+            Box::new((0..16).map(|i| Vertex {
+                pos: nalgebra::Vector4::from_fn(|j, _| if i >> j & 1 == 0 { -1. } else { 1. }),
+                texcoord: [0., 0.],
+                texture: None,
+                color: [1.; 4],
+                normal: nalgebra::Vector4::zeros(),
+            }))
+        }
+
+        fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+            let region = (0..8)
+                .map(|i| {
+                    let mut h = nalgebra::RowVector5::zeros();
+                    h[i / 2] = if i % 2 == 0 { 1. } else { -1. };
+                    h[4] = -1.;
+                    h
+                })
+                .collect();
+            Box::new(std::iter::once(region))
+        }
+    }
+
+    fn assert_close(a: nalgebra::Vector4<f32>, b: nalgebra::Vector4<f32>) {
+        assert!((a - b).norm() < 1e-5, "{} != {}", a, b);
+    }
+
+    fn positions(object: &impl Renderable) -> Vec<nalgebra::Vector4<f32>> {
+        object.triangles().map(|v| v.pos).collect()
+    }
+
+    /// Every vertex of `object` lies in or on the boundary of each of its regions.
+    fn assert_regions_contain_vertices(object: &impl Renderable) {
+        for region in object.regions() {
+            for p in positions(object) {
+                for h in &region {
+                    assert!(
+                        (h * p.insert_row(4, 1.))[0] < 1e-4,
+                        "{} is outside {}",
+                        p,
+                        h
+                    );
+                }
+            }
+        }
+    }
+
+    fn doubled<R>(inner: R) -> Transform<R> {
+        Transform::nonuniform_scaling(inner, nalgebra::Vector4::repeat(2.)).unwrap()
+    }
+
+    #[test]
+    fn rotation_turns_the_first_axis_towards_the_second() {
+        let rotated = Transform::identity(Cube).rotate((0, 2), PI / 2.).unwrap();
+        let expected: Vec<_> = positions(&Cube)
+            .into_iter()
+            .map(|p| nalgebra::Vector4::new(-p[2], p[1], p[0], p[3]))
+            .collect();
+        for (a, b) in positions(&rotated).into_iter().zip(expected) {
+            assert_close(a, b);
+        }
+        assert_regions_contain_vertices(&rotated);
+    }
+
+    #[test]
+    fn nonuniform_scaling_scales_each_axis() {
+        let factors = nalgebra::Vector4::new(2., -0.5, 1., 3.);
+        let scaled = Transform::nonuniform_scaling(Cube, factors).unwrap();
+        for (a, b) in positions(&scaled).into_iter().zip(positions(&Cube)) {
+            assert_close(a, b.component_mul(&factors));
+        }
+        assert_regions_contain_vertices(&scaled);
+    }
+
+    #[test]
+    fn invalid_transforms_are_rejected() {
+        let mut singular = nalgebra::Matrix5::identity();
+        singular[(1, 1)] = 0.;
+        assert!(Transform::new(Cube, singular).is_none());
+        assert!(Transform::identity(Cube).rotate((2, 2), 1.).is_none());
+        assert!(Transform::identity(Cube).rotate((1, 4), 1.).is_none());
+        assert!(doubled(Cube).rotate((4, 0), 1.).is_none());
+        let flattened = nalgebra::Vector4::new(1., 0., 1., 1.);
+        assert!(Transform::nonuniform_scaling(Cube, flattened).is_none());
+        assert!(doubled(Cube).scale(flattened).is_none());
+
+        let mut shear = nalgebra::Matrix5::identity();
+        shear[(0, 3)] = 2.;
+        let sheared = Transform::new(Cube, shear).unwrap();
+        assert_regions_contain_vertices(&sheared);
+    }
+
+    #[test]
+    fn composed_transforms_apply_in_order() {
+        let composed = doubled(Cube)
+            .rotate((1, 3), 0.7)
+            .unwrap()
+            .translate(nalgebra::Vector4::new(1., -2., 3., 0.5));
+        let by_hand = Transform::identity(
+            Transform::identity(doubled(Cube))
+                .rotate((1, 3), 0.7)
+                .unwrap(),
+        )
+        .translate(nalgebra::Vector4::new(1., -2., 3., 0.5));
+        for (a, b) in positions(&composed).into_iter().zip(positions(&by_hand)) {
+            assert_close(a, b);
+        }
+        assert_regions_contain_vertices(&composed);
+
+        // A point just outside the original cube stays outside after transforming.
+        let outside = composed.transform * nalgebra::Vector5::new(1.01, 0., 0., 0., 1.);
+        assert!(composed
+            .regions()
+            .all(|r| r.iter().any(|h| (h * outside)[0] > 0.)));
+    }
+
+    #[test]
+    fn collections_render_each_element() {
+        let counts =
+            |object: &dyn Renderable| (object.triangles().count(), object.regions().count());
+        let one = counts(&Cube);
+
+        assert_eq!(counts(&[Cube, Cube, Cube]), (3 * one.0, 3 * one.1));
+        assert_eq!(counts(&vec![Cube, Cube]), (2 * one.0, 2 * one.1));
+        assert_eq!(counts(&Vec::<Cube>::new()), (0, 0));
+        assert_eq!(counts(&Some(Cube)), one);
+        assert_eq!(counts(&None::<Cube>), (0, 0));
+
+        // Boxes let different kinds of object share a collection, in order.
+        let mixed: Vec<Box<dyn Renderable>> = vec![Box::new(Cube), Box::new(doubled(Cube))];
+        assert_eq!(counts(&mixed), (2 * one.0, 2 * one.1));
+        let farthest: Vec<f32> = mixed.triangles().map(|v| v.pos.amax()).collect();
+        assert!(farthest[..one.0].iter().all(|&x| x == 1.));
+        assert!(farthest[one.0..].iter().all(|&x| x == 2.));
+    }
+
+    #[test]
+    fn normals_face_away_from_inside() {
+        let triangle = [
+            nalgebra::Vector4::new(0., 0., 0., 0.),
+            nalgebra::Vector4::new(0., 1., 0., 0.),
+            nalgebra::Vector4::new(0., 0., 1., 0.),
+        ];
+        assert_close(
+            normal(triangle, nalgebra::Vector4::new(-1., 0.5, 0.5, -1.)),
+            nalgebra::Vector4::new(1., 0., 0., 1.).normalize(),
+        );
+        // Degenerate triangles, and points in the triangle's plane, give no normal.
+        assert_eq!(
+            normal(triangle, nalgebra::Vector4::new(0., 3., 3., 0.)),
+            nalgebra::Vector4::zeros()
+        );
+        assert_eq!(
+            normal(
+                [triangle[0], triangle[1], triangle[1] * 2.],
+                -nalgebra::Vector4::x()
+            ),
+            nalgebra::Vector4::zeros()
+        );
+    }
+
+    #[test]
+    fn transformed_normals_stay_orthogonal_to_their_triangles() {
+        let rotated = Transform::identity(Polytope::tesseract())
+            .rotate((1, 3), 0.4)
+            .unwrap()
+            .translate(nalgebra::Vector4::new(1., 2., 3., 4.));
+        let rotation = Transform::identity(Polytope::tesseract())
+            .rotate((1, 3), 0.4)
+            .unwrap()
+            .transform;
+        for (v, w) in rotated.triangles().zip(Polytope::tesseract().triangles()) {
+            assert_close(
+                v.normal,
+                rotation.fixed_slice::<nalgebra::U4, nalgebra::U4>(0, 0) * w.normal,
+            );
+        }
+
+        let stretched = rotated
+            .scale(nalgebra::Vector4::new(1., 3., 0.5, 2.))
+            .unwrap();
+        let vertices: Vec<_> = stretched.triangles().collect();
+        for triangle in vertices.chunks_exact(3) {
+            let n = triangle[0].normal;
+            assert!((n.norm() - 1.).abs() < 1e-5);
+            for v in triangle {
+                assert_eq!(v.normal, n);
+                assert!(n.dot(&(v.pos - triangle[0].pos)).abs() < 1e-4);
+            }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use super::{Renderable, Transform, Vertex};
+use std::collections::BTreeSet;
+
+/// Identifies an object within a [`Scene`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ObjectId(usize);
+
+/// A collection of independently transformed objects, which can be changed between frames.
+///
+/// The scene remembers which objects have changed,
+/// so the renderer only has to re-upload the geometry of those objects.
+#[derive(Default)]
+pub struct Scene {
+    objects: Vec<Option<Transform<Box<dyn Renderable>>>>,
+    dirty: BTreeSet<usize>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, renderable: impl 'static + Renderable) -> ObjectId {
+        let object = Some(Transform::identity(
+            Box::new(renderable) as Box<dyn Renderable>
+        ));
+
+        let index = match self.objects.iter().position(Option::is_none) {
+            Some(index) => {
+                self.objects[index] = object;
+                index
+            }
+            None => {
+                self.objects.push(object);
+                self.objects.len() - 1
+            }
+        };
+
+        self.dirty.insert(index);
+        ObjectId(index)
+    }
+
+    pub fn remove(&mut self, id: ObjectId) -> Option<Box<dyn Renderable>> {
+        let object = self.objects.get_mut(id.0)?.take()?;
+        self.dirty.insert(id.0);
+        Some(object.into_inner())
+    }
+
+    /// Set the homogeneous matrix by which an object is transformed.
+    /// Returns `None` if there is no such object, or the matrix is singular.
+    pub fn set_transform(&mut self, id: ObjectId, transform: nalgebra::Matrix5<f32>) -> Option<()> {
+        self.objects
+            .get_mut(id.0)?
+            .as_mut()?
+            .set_matrix(transform)?;
+        self.dirty.insert(id.0);
+        Some(())
+    }
+
+    /// The indices of the objects changed since this was last called.
+    pub(super) fn take_dirty(&mut self) -> BTreeSet<usize> {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// The transformed triangles of the object at `index`, or nothing if it has been removed.
+    pub(super) fn triangles_of(&self, index: usize) -> Vec<Vertex> {
+        match self.objects.get(index) {
+            Some(Some(object)) => object.triangles().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Scene {
+    /// The transformed regions of the object at `index`, or nothing if it has been removed.
+    pub(super) fn regions_of(&self, index: usize) -> Vec<Vec<nalgebra::RowVector5<f32>>> {
+        match self.objects.get(index) {
+            Some(Some(object)) => object.regions().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Renderable for Scene {
+    fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+        Box::new(self.objects.iter().flatten().flat_map(|o| o.triangles()))
+    }
+
+    fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+        Box::new(self.objects.iter().flatten().flat_map(|o| o.regions()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polytope::Polytope;
+
+    fn dirty(scene: &mut Scene) -> Vec<usize> {
+        scene.take_dirty().into_iter().collect()
+    }
+
+    #[test]
+    fn changed_objects_are_dirty() {
+        let mut scene = Scene::new();
+        let a = scene.add(Polytope::tesseract());
+        let b = scene.add(Polytope::five_cell());
+        assert_eq!(dirty(&mut scene), vec![0, 1]);
+        assert_eq!(dirty(&mut scene), vec![]);
+
+        let mut moved = nalgebra::Matrix5::identity();
+        moved[(0, 4)] = 2.;
+        scene.set_transform(b, moved).unwrap();
+        assert_eq!(dirty(&mut scene), vec![1]);
+        assert!(scene.triangles_of(1).iter().all(|v| v.pos[0] > 1.));
+        // A singular matrix is rejected, leaving the object where it was.
+        assert!(scene.set_transform(b, nalgebra::Matrix5::zeros()).is_none());
+        assert_eq!(dirty(&mut scene), vec![]);
+
+        scene.remove(a).unwrap();
+        assert_eq!(scene.add(Polytope::sixteen_cell()), a);
+        assert_eq!(dirty(&mut scene), vec![0]);
+        assert_eq!(scene.regions_of(0)[0].len(), 16);
+    }
+
+    #[test]
+    fn removed_slots_are_reused() {
+        let mut scene = Scene::new();
+        let a = scene.add(Polytope::tesseract());
+        scene.add(Polytope::tesseract());
+        assert!(scene.remove(a).is_some());
+        assert!(scene.remove(a).is_none());
+        assert!(scene.triangles_of(0).is_empty());
+        assert!(scene
+            .set_transform(a, nalgebra::Matrix5::identity())
+            .is_none());
+
+        assert_eq!(scene.add(Polytope::five_cell()), a);
+        assert_eq!(
+            scene.triangles().count(),
+            Polytope::five_cell().triangles().count() + Polytope::tesseract().triangles().count()
+        );
+    }
+}
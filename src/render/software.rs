@@ -0,0 +1,582 @@
+//! A software implementation of the renderer.
+//!
+//! This follows the shaders in `to_tex` and `from_tex` step by step,
+//! so that what they draw can be checked without WebGL.
+
+use super::bvh::{self, Bvh};
+use super::texture::Atlas;
+use super::to_tex::{self, Uniforms, Vertex, FLOATS_PER_HALFSPACE};
+use super::Renderable;
+
+/// An RGBA image, stored row by row from the top.
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<[f32; 4]>,
+}
+
+impl Image {
+    fn new(width: usize, height: usize, color: [f32; 4]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![color; width * height],
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [f32; 4] {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn pixels(&self) -> &[[f32; 4]] {
+        &self.pixels
+    }
+
+    /// The pixel at window coordinates `(x, y)`, which, as in OpenGL, count up from the bottom left.
+    fn window_pixel_mut(&mut self, x: usize, y: usize) -> &mut [f32; 4] {
+        &mut self.pixels[(self.height - 1 - y) * self.width + x]
+    }
+}
+
+/// Render `renderable` as the canvas would show it,
+/// with each of the two three-cameras drawing to a `size` by `size` square.
+pub fn render(renderable: &(impl ?Sized + Renderable), uniforms: &Uniforms, size: usize) -> Image {
+    let (halfspaces, regions) = to_tex::region_data(renderable.regions());
+    let hierarchy = Bvh::new(
+        regions
+            .iter()
+            .map(|r| bvh::Leaf {
+                first: r.offset,
+                count: r.count,
+                min: r.min,
+                max: r.max,
+            })
+            .collect(),
+    );
+    let scene = Occluders {
+        halfspaces: &halfspaces,
+        hierarchy: &hierarchy,
+    };
+
+    let triangles: Vec<Vertex> = renderable.triangles().collect();
+    let mut atlas = Atlas::new();
+    let texcoords: Vec<[f32; 2]> = triangles.iter().map(|v| atlas.texcoord(v)).collect();
+
+    // The intermediate texture, cleared to black.
+    let mut image = Image::new(2 * size, size, [0., 0., 0., 1.]);
+
+    for (viewport, three_camera) in uniforms.three_cameras.iter().enumerate() {
+        for (triangle, texcoords) in triangles.chunks_exact(3).zip(texcoords.chunks_exact(3)) {
+            let polygon = triangle
+                .iter()
+                .zip(texcoords)
+                .map(|(v, &texcoord)| vertex_shader(v, texcoord, uniforms, three_camera))
+                .collect();
+            let polygon = clip_polygon(polygon);
+            for i in 2..polygon.len() {
+                rasterize(
+                    &mut image,
+                    viewport * size,
+                    size,
+                    [&polygon[0], &polygon[i - 1], &polygon[i]],
+                    |varyings| fragment_shader(varyings, uniforms, &scene, &atlas),
+                );
+            }
+        }
+    }
+
+    // The final pass, from the intermediate texture to the canvas.
+    for pixel in &mut image.pixels {
+        for c in pixel.iter_mut() {
+            *c = (-*c).exp();
+        }
+    }
+
+    image
+}
+
+/// The output of the vertex shader for one vertex.
+#[derive(Clone)]
+struct ShadedVertex {
+    /// `gl_Position`.
+    position: nalgebra::Vector4<f32>,
+    /// `vpos`, `vtexcoord`, `vdata`, `vcolor` and `vnormal`, one after another.
+    varyings: [f32; VARYINGS],
+}
+
+/// The number of floats passed from the vertex shader to the fragment shader.
+const VARYINGS: usize = 18;
+
+/// The vertex shader, given the vertex's texture coordinates within the atlas, in texels.
+fn vertex_shader(
+    vertex: &Vertex,
+    texcoord: [f32; 2],
+    uniforms: &Uniforms,
+    three_camera: &nalgebra::Matrix4<f32>,
+) -> ShadedVertex {
+    let a = uniforms
+        .four_camera
+        .fixed_slice::<nalgebra::U4, nalgebra::U4>(0, 0);
+    let b = uniforms.four_camera.column(4);
+    let vdata = a * vertex.pos + b;
+
+    let mut varyings = [0.; VARYINGS];
+    varyings[0..4].copy_from_slice(vertex.pos.as_slice());
+    varyings[4..6].copy_from_slice(&texcoord);
+    varyings[6..10].copy_from_slice(vdata.as_slice());
+    varyings[10..14].copy_from_slice(&vertex.color);
+    varyings[14..18].copy_from_slice(vertex.normal.as_slice());
+
+    ShadedVertex {
+        position: three_camera * nalgebra::Vector4::new(vdata.y, vdata.x, vdata.z, vdata.w),
+        varyings,
+    }
+}
+
+fn lerp(a: &ShadedVertex, b: &ShadedVertex, t: f32) -> ShadedVertex {
+    let mut varyings = [0.; VARYINGS];
+    for (i, v) in varyings.iter_mut().enumerate() {
+        *v = a.varyings[i] + (b.varyings[i] - a.varyings[i]) * t;
+    }
+    ShadedVertex {
+        position: a.position.lerp(&b.position, t),
+        varyings,
+    }
+}
+
+/// Clip a convex polygon to the view volume, `-w <= x, y, z <= w`.
+fn clip_polygon(mut polygon: Vec<ShadedVertex>) -> Vec<ShadedVertex> {
+    for axis in 0..3 {
+        for &sign in &[-1., 1.] {
+            let distance = |v: &ShadedVertex| v.position.w - sign * v.position[axis];
+
+            let mut clipped = Vec::with_capacity(polygon.len() + 1);
+            for (i, a) in polygon.iter().enumerate() {
+                let b = &polygon[(i + 1) % polygon.len()];
+                let (da, db) = (distance(a), distance(b));
+                if da >= 0. {
+                    clipped.push(a.clone());
+                }
+                if (da >= 0.) != (db >= 0.) {
+                    clipped.push(lerp(a, b, da / (da - db)));
+                }
+            }
+            polygon = clipped;
+        }
+    }
+    polygon
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p`; positive if it is counterclockwise.
+fn edge(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+/// Whether pixel centers lying exactly on the edge from `a` to `b`
+/// belong to the triangle to its left.
+/// Exactly one of two triangles sharing an edge gets those pixels, so they are not drawn twice.
+fn owns_edge(a: [f32; 2], b: [f32; 2]) -> bool {
+    b[1] > a[1] || (b[1] == a[1] && b[0] < a[0])
+}
+
+/// Draw a clipped triangle into the `size` by `size` viewport whose left edge is at `left`,
+/// adding the fragment shader's output to the image.
+fn rasterize(
+    image: &mut Image,
+    left: usize,
+    size: usize,
+    triangle: [&ShadedVertex; 3],
+    mut fragment_shader: impl FnMut(&[f32; VARYINGS]) -> [f32; 4],
+) {
+    let window = |v: &ShadedVertex| {
+        [
+            (v.position.x / v.position.w + 1.) / 2. * size as f32,
+            (v.position.y / v.position.w + 1.) / 2. * size as f32,
+        ]
+    };
+    let mut triangle = triangle;
+    let mut points = [
+        window(triangle[0]),
+        window(triangle[1]),
+        window(triangle[2]),
+    ];
+
+    let area = edge(points[0], points[1], points[2]);
+    if area == 0. || !area.is_finite() {
+        return;
+    }
+    if area < 0. {
+        triangle.swap(1, 2);
+        points.swap(1, 2);
+    }
+    let area = area.abs();
+
+    let range = |axis: usize| {
+        let min = points.iter().map(|p| p[axis]).fold(f32::INFINITY, f32::min);
+        let max = points
+            .iter()
+            .map(|p| p[axis])
+            .fold(f32::NEG_INFINITY, f32::max);
+        let first = (min - 0.5).ceil().max(0.) as usize;
+        let last = ((max - 0.5).floor() + 1.).max(0.).min(size as f32) as usize;
+        first..last
+    };
+
+    for y in range(1) {
+        for x in range(0) {
+            let p = [x as f32 + 0.5, y as f32 + 0.5];
+
+            let mut weights = [0.; 3];
+            let mut inside = true;
+            for i in 0..3 {
+                let (a, b) = (points[(i + 1) % 3], points[(i + 2) % 3]);
+                let e = edge(a, b, p);
+                inside &= e > 0. || (e == 0. && owns_edge(a, b));
+                // Perspective-correct interpolation.
+                weights[i] = e / area / triangle[i].position.w;
+            }
+            if !inside {
+                continue;
+            }
+
+            let total: f32 = weights.iter().sum();
+            let mut varyings = [0.; VARYINGS];
+            for (i, v) in varyings.iter_mut().enumerate() {
+                *v = (0..3)
+                    .map(|j| weights[j] * triangle[j].varyings[i])
+                    .sum::<f32>()
+                    / total;
+            }
+
+            let color = fragment_shader(&varyings);
+            let pixel = image.window_pixel_mut(left + x, y);
+            for (c, d) in pixel.iter_mut().zip(color.iter()) {
+                *c += d;
+            }
+        }
+    }
+}
+
+fn fragment_shader(
+    varyings: &[f32; VARYINGS],
+    uniforms: &Uniforms,
+    scene: &Occluders,
+    atlas: &Atlas,
+) -> [f32; 4] {
+    let vpos = nalgebra::Vector4::from_column_slice(&varyings[0..4]);
+    let vtexcoord = [varyings[4], varyings[5]];
+    let vdata = nalgebra::Vector4::from_column_slice(&varyings[6..10]);
+    let vcolor = &varyings[10..14];
+    let vnormal = nalgebra::Vector4::from_column_slice(&varyings[14..18]);
+
+    let data = vdata.xyz() / vdata.w;
+    let size = uniforms.three_screen_size;
+
+    let mut eye = uniforms.four_camera_pos;
+    let depth = (vpos - uniforms.four_camera_pos).dot(&uniforms.four_camera_forward);
+    if uniforms.orthographic {
+        eye = vpos - depth * uniforms.four_camera_forward;
+    }
+
+    if data.x.abs() > size[0] || data.y.abs() > size[1] || data.z.abs() > size[2] {
+        // Outside three-screen, so invisible.
+        [0.; 4]
+    } else if uniforms.orthographic && depth < 0. {
+        // Behind the camera, so invisible.
+        [0.; 4]
+    } else if scene.intersects(&eye, &vpos) {
+        // Occluded, so invisible.
+        [0.; 4]
+    } else {
+        let mut shade = 1.;
+        if uniforms.light != nalgebra::Vector4::zeros() && vnormal != nalgebra::Vector4::zeros() {
+            shade = vnormal.normalize().dot(&uniforms.light).max(0.);
+        }
+        let texel = texture(atlas, vtexcoord);
+        let opacity = vcolor[3] * (2. - shade) / 5.;
+        [
+            texel[0] * vcolor[0] * opacity,
+            texel[1] * vcolor[1] * opacity,
+            texel[2] * vcolor[2] * opacity,
+            texel[3] * opacity,
+        ]
+    }
+}
+
+/// Sample the atlas at a position given in texels, with nearest filtering.
+fn texture(atlas: &Atlas, texcoord: [f32; 2]) -> [f32; 4] {
+    let index = |t: f32, size: usize| (t.floor().max(0.) as usize).min(size - 1);
+    let texel = atlas.texel(
+        index(texcoord[0], atlas.width()),
+        index(texcoord[1], atlas.height()),
+    );
+    let mut color = [0.; 4];
+    for (c, &byte) in color.iter_mut().zip(&texel) {
+        *c = f32::from(byte) / 255.;
+    }
+    color
+}
+
+/// The regions of the scene, laid out as in the region and hierarchy textures.
+struct Occluders<'a> {
+    halfspaces: &'a [f32],
+    hierarchy: &'a Bvh,
+}
+
+impl Occluders<'_> {
+    fn intersects(&self, pos: &nalgebra::Vector4<f32>, target: &nalgebra::Vector4<f32>) -> bool {
+        self.hierarchy.any(
+            |lo, hi| intersects_box(pos, target, lo, hi),
+            |first, count| self.intersects_region(pos, target, first, count),
+        )
+    }
+
+    fn intersects_region(
+        &self,
+        pos: &nalgebra::Vector4<f32>,
+        target: &nalgebra::Vector4<f32>,
+        first: usize,
+        count: usize,
+    ) -> bool {
+        let mut minmax = [0., 0.999];
+
+        for i in first..first + count {
+            let h = &self.halfspaces[i * FLOATS_PER_HALFSPACE..];
+            let abcd = nalgebra::Vector4::new(h[0], h[1], h[2], h[3]);
+            minmax = clip(minmax, pos, target, &abcd, h[4]);
+        }
+
+        minmax[1] > minmax[0]
+    }
+}
+
+fn clip(
+    mut minmax: [f32; 2],
+    pos: &nalgebra::Vector4<f32>,
+    target: &nalgebra::Vector4<f32>,
+    abcd: &nalgebra::Vector4<f32>,
+    e: f32,
+) -> [f32; 2] {
+    let x = abcd.dot(pos) + e;
+    let y = abcd.dot(target) + e;
+
+    if x > y {
+        minmax[0] = minmax[0].max(x / (x - y));
+    } else {
+        minmax[1] = minmax[1].min(x / (x - y));
+    }
+
+    minmax
+}
+
+fn intersects_box(
+    pos: &nalgebra::Vector4<f32>,
+    target: &nalgebra::Vector4<f32>,
+    lo: &nalgebra::Vector4<f32>,
+    hi: &nalgebra::Vector4<f32>,
+) -> bool {
+    let mut minmax = [0., 0.999_f32];
+
+    for i in 0..4 {
+        let d = target[i] - pos[i];
+        if d.abs() < 1e-20 {
+            if pos[i] < lo[i] || pos[i] > hi[i] {
+                return false;
+            }
+        } else {
+            let a = (lo[i] - pos[i]) / d;
+            let b = (hi[i] - pos[i]) / d;
+            minmax[0] = minmax[0].max(a.min(b));
+            minmax[1] = minmax[1].min(a.max(b));
+        }
+    }
+
+    minmax[1] >= minmax[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polytope::Polytope;
+    use crate::render::{Texture, Textured, Tinted, Transform};
+    use std::rc::Rc;
+
+    /// The uniforms for the default camera, at the origin looking down the `-w` axis.
+    #[rustfmt::skip]
+    fn uniforms() -> Uniforms {
+        let x = 1. / (1.57_f32 / 2.).tan();
+        let three_camera = nalgebra::Matrix4::new(
+            1., 0., 0., 0.,
+            0., 1., 0., 0.,
+            0., 0., -1., 2.98,
+            0., 0., -1., 3.,
+        );
+        Uniforms {
+            four_camera: nalgebra::Matrix4x5::new(
+                x, 0., 0., 0., 0.,
+                0., x, 0., 0., 0.,
+                0., 0., x, 0., 0.,
+                0., 0., 0., -1., 0.,
+            ),
+            four_camera_pos: nalgebra::Vector4::zeros(),
+            four_camera_forward: nalgebra::Vector4::new(0., 0., 0., -1.),
+            orthographic: false,
+            three_cameras: [three_camera, three_camera],
+            three_screen_size: [1., 1., 1.],
+            light: nalgebra::Vector4::zeros(),
+        }
+    }
+
+    /// A gray square in front of the camera, facing it.
+    struct Square;
+
+    impl Renderable for Square {
+        fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+            let texture = Rc::new(Texture::solid([128, 128, 128, 255]));
+            let vertex = move |x, y| Vertex {
+                pos: nalgebra::Vector4::new(x, y, 0., -2.),
+                texcoord: [0.5, 0.5],
+                texture: Some(Rc::clone(&texture)),
+                color: [1.; 4],
+                normal: nalgebra::Vector4::new(0., 0., 0., 1.),
+            };
+            Box::new(
+                vec![
+                    vertex(-1., -1.),
+                    vertex(1., -1.),
+                    vertex(1., 1.),
+                    vertex(-1., -1.),
+                    vertex(1., 1.),
+                    vertex(-1., 1.),
+                ]
+                .into_iter(),
+            )
+        }
+
+        fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    /// A region with no faces, filling `-1.5 < w < -1`.
+    struct Wall;
+
+    impl Renderable for Wall {
+        fn triangles<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vertex>> {
+            Box::new(std::iter::empty())
+        }
+
+        fn regions<'r>(&'r self) -> Box<dyn 'r + Iterator<Item = Vec<nalgebra::RowVector5<f32>>>> {
+            let mut region = vec![
+                nalgebra::RowVector5::new(0., 0., 0., 1., 1.),
+                nalgebra::RowVector5::new(0., 0., 0., -1., -1.5),
+            ];
+            for i in 0..3 {
+                let mut h = nalgebra::RowVector5::zeros();
+                h[i] = 1.;
+                h[4] = -10.;
+                region.push(h);
+                h[i] = -1.;
+                region.push(h);
+            }
+            Box::new(std::iter::once(region))
+        }
+    }
+
+    fn tesseract() -> Transform<Polytope> {
+        Transform::identity(Polytope::tesseract())
+            .translate(nalgebra::Vector4::new(0., 0., 0., -2.))
+    }
+
+    fn is_white(pixel: [f32; 4]) -> bool {
+        pixel[..3].iter().all(|&c| c == 1.)
+    }
+
+    #[test]
+    fn empty_scene_is_white() {
+        let image = render(&Vec::<Polytope>::new(), &uniforms(), 16);
+        assert_eq!((image.width, image.height), (32, 16));
+        assert!(image.pixels().iter().all(|&p| is_white(p)));
+    }
+
+    #[test]
+    fn both_eyes_see_the_object_in_front() {
+        let image = render(&tesseract(), &uniforms(), 32);
+        assert!(!is_white(image.pixel(16, 16)));
+        assert!(!is_white(image.pixel(48, 16)));
+        assert_eq!(image.pixel(16, 16), image.pixel(48, 16));
+        assert!(is_white(image.pixel(0, 0)));
+        assert!(is_white(image.pixel(63, 31)));
+    }
+
+    #[test]
+    fn objects_are_painted_with_their_own_textures() {
+        // Texels say how much light is absorbed, so this texture is invisible.
+        let clear = Rc::new(Texture::solid([0; 4]));
+        let textured = Textured {
+            inner: tesseract(),
+            texture: clear,
+        };
+        assert!(!is_white(
+            render(&tesseract(), &uniforms(), 32).pixel(16, 16)
+        ));
+        let image = render(&textured, &uniforms(), 32);
+        assert!(image.pixels().iter().all(|&p| is_white(p)));
+    }
+
+    #[test]
+    fn tints_and_opacity() {
+        let tinted = |color| {
+            render(
+                &Tinted {
+                    inner: tesseract(),
+                    color,
+                },
+                &uniforms(),
+                32,
+            )
+            .pixel(16, 16)
+        };
+        let plain = tinted([1.; 4]);
+        // With no red absorbed, the object looks red.
+        let red = tinted([0., 1., 1., 1.]);
+        assert_eq!(red[0], 1.);
+        assert_eq!(red[1..3], plain[1..3]);
+        assert!(is_white(tinted([1., 1., 1., 0.])));
+        // Half as opaque absorbs half as much.
+        let faint = tinted([1., 1., 1., 0.5]);
+        assert!((faint[1].ln() - plain[1].ln() / 2.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn faces_turned_away_from_the_light_are_darker() {
+        let lit = |light| {
+            let mut uniforms = uniforms();
+            uniforms.light = light;
+            render(&Square, &uniforms, 32).pixel(16, 16)
+        };
+        let unlit = lit(nalgebra::Vector4::zeros());
+        assert!(!is_white(unlit));
+        // Facing the light looks the same as being unlit, and facing away absorbs twice as much.
+        assert_eq!(lit(nalgebra::Vector4::new(0., 0., 0., 1.)), unlit);
+        let away = lit(nalgebra::Vector4::new(0., 0., 0., -1.));
+        assert!((away[1].ln() - 2. * unlit[1].ln()).abs() < 1e-4);
+        let oblique = lit(nalgebra::Vector4::new(1., 0., 0., 1.).normalize());
+        assert!(away[1] < oblique[1] && oblique[1] < unlit[1]);
+        assert_eq!(lit(nalgebra::Vector4::new(1., 0., 0., 0.)), away);
+    }
+
+    #[test]
+    fn regions_occlude() {
+        let image = render(&(tesseract(), Wall), &uniforms(), 32);
+        assert!(image.pixels().iter().all(|&p| is_white(p)));
+    }
+
+    #[test]
+    fn nothing_is_drawn_outside_the_three_screen() {
+        let mut uniforms = uniforms();
+        uniforms.three_screen_size = [0.; 3];
+        let image = render(&tesseract(), &uniforms, 32);
+        assert!(image.pixels().iter().all(|&p| is_white(p)));
+    }
+}
@@ -0,0 +1,344 @@
+use super::Vertex;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// The texture painting every triangle which does not have one of its own.
+const DEFAULT_TEXTURE: &str = include_str!("../../resources/ascii_art_texture.txt");
+
+/// The colors of the characters in `DEFAULT_TEXTURE`.
+const DEFAULT_PALETTE: &[(char, [u8; 4])] = &[
+    ('@', [128, 192, 255, 255]),
+    (':', [144, 200, 255, 255]),
+    (' ', [160, 208, 255, 255]),
+    ('.', [255, 255, 255, 255]),
+    ('0', [255, 192, 255, 255]),
+    ('1', [255, 176, 255, 255]),
+    ('2', [255, 160, 255, 255]),
+    ('3', [255, 144, 255, 255]),
+    ('4', [255, 128, 255, 255]),
+    ('5', [255, 112, 255, 255]),
+    ('6', [255, 96, 255, 255]),
+    ('7', [255, 80, 255, 255]),
+];
+
+/// The bark of trees, in the same palette as `DEFAULT_TEXTURE`.
+const BARK_TEXTURE: &str = include_str!("../../resources/textures/bark.txt");
+
+/// The foliage of trees, shading smoothly from dark at `u = 0` to light at `u = 1`, in more shades than a palette would hold.
+const FOLIAGE_TEXTURE: &[u8] = include_bytes!("../../resources/textures/foliage.png");
+
+thread_local! {
+    /// The textures which can be referred to by name.
+    ///
+    /// They are shared, so each is only added to the atlas once, however many objects use it.
+    static NAMED: HashMap<&'static str, Rc<Texture>> = {
+        use crate::format::texture::{read_ascii_art, read_png};
+        let mut named = HashMap::new();
+        named.insert("ground", Rc::new(Texture::solid([255, 80, 255, 255])));
+        named.insert(
+            "bark",
+            Rc::new(read_ascii_art(BARK_TEXTURE, DEFAULT_PALETTE).expect("the bark texture is valid")),
+        );
+        named.insert(
+            "foliage",
+            Rc::new(
+                read_png(FOLIAGE_TEXTURE)
+                    .expect("the foliage texture is valid"),
+            ),
+        );
+        named
+    };
+}
+
+/// The width of the atlas, in texels.
+const ATLAS_WIDTH: usize = 1024;
+
+/// An RGBA image, with one byte per channel and the rows of texels stored from `v = 0` to `v = 1`.
+#[derive(Debug, PartialEq)]
+pub struct Texture {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Default for Texture {
+    fn default() -> Self {
+        crate::format::texture::read_ascii_art(DEFAULT_TEXTURE, DEFAULT_PALETTE)
+            .expect("the default texture is valid")
+    }
+}
+
+impl Texture {
+    /// The widest texture which fits in the atlas, alongside its border.
+    pub const MAX_WIDTH: usize = ATLAS_WIDTH - 2;
+
+    /// A texture of the given size, from four bytes per texel.
+    pub fn new(width: usize, height: usize, pixels: Vec<u8>) -> Self {
+        assert!(width > 0 && height > 0, "textures must not be empty");
+        assert!(width <= Self::MAX_WIDTH, "texture too wide for the atlas");
+        assert_eq!(pixels.len(), width * height * 4, "wrong number of pixels");
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// The texture called `name`: one of `ground`, `bark` and `foliage`.
+    pub fn named(name: &str) -> Option<Rc<Self>> {
+        NAMED.with(|named| named.get(name).cloned())
+    }
+
+    /// A texture of a single texel.
+    pub fn solid(color: [u8; 4]) -> Self {
+        Self::new(1, 1, color.to_vec())
+    }
+
+    #[cfg(test)]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[cfg(test)]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The texel in column `x` and row `y`.
+    pub fn texel(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = 4 * (y * self.width + x);
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+}
+
+/// All the textures in use, packed side by side into one image, so they can be drawn in a single pass.
+///
+/// Each texture is surrounded by a border one texel wide, copied from its edges,
+/// so that sampling just outside it behaves as if its edges were clamped.
+/// Textures never move once placed, so texture coordinates stay valid as more textures are added.
+pub(super) struct Atlas {
+    pixels: Vec<u8>,
+    /// The position of each texture's first texel, keyed by the address of the texture.
+    origins: HashMap<*const Texture, [usize; 2]>,
+    /// Keeps the textures alive, so their addresses are not reused.
+    textures: Vec<Rc<Texture>>,
+    default: Rc<Texture>,
+    /// Textures are placed left to right along shelves, each as tall as the tallest texture on it.
+    shelf: Shelf,
+    changed: bool,
+}
+
+#[derive(Default)]
+struct Shelf {
+    top: usize,
+    height: usize,
+    /// Where the next texture on this shelf will go.
+    left: usize,
+}
+
+impl Atlas {
+    pub fn new() -> Self {
+        let default = Rc::new(Texture::default());
+        let mut atlas = Self {
+            pixels: Vec::new(),
+            origins: HashMap::new(),
+            textures: Vec::new(),
+            default: Rc::clone(&default),
+            shelf: Shelf::default(),
+            changed: true,
+        };
+        atlas.insert(&default);
+        atlas
+    }
+
+    pub fn width(&self) -> usize {
+        ATLAS_WIDTH
+    }
+
+    pub fn height(&self) -> usize {
+        self.pixels.len() / (4 * ATLAS_WIDTH)
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Whether textures were added since this was last called.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// The texel in column `x` and row `y`.
+    #[cfg(test)]
+    pub fn texel(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = 4 * (y * ATLAS_WIDTH + x);
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    /// Where `vertex` samples the atlas, in texels, adding its texture if it is not there yet.
+    ///
+    /// Texture coordinates outside the unit square are clamped to it.
+    pub fn texcoord(&mut self, vertex: &Vertex) -> [f32; 2] {
+        let texture = Rc::clone(vertex.texture.as_ref().unwrap_or(&self.default));
+        let origin = self.insert(&texture);
+        let size = [texture.width, texture.height];
+        let mut out = [0.; 2];
+        for i in 0..2 {
+            out[i] = origin[i] as f32 + vertex.texcoord[i].clamp(0., 1.) * size[i] as f32;
+        }
+        out
+    }
+
+    fn insert(&mut self, texture: &Rc<Texture>) -> [usize; 2] {
+        if let Some(&origin) = self.origins.get(&Rc::as_ptr(texture)) {
+            return origin;
+        }
+
+        let (width, height) = (texture.width + 2, texture.height + 2);
+        if self.shelf.left + width > ATLAS_WIDTH {
+            self.shelf = Shelf {
+                top: self.shelf.top + self.shelf.height,
+                height: 0,
+                left: 0,
+            };
+        }
+        let (left, top) = (self.shelf.left, self.shelf.top);
+        self.shelf.left += width;
+        self.shelf.height = self.shelf.height.max(height);
+        self.pixels.resize(
+            4 * ATLAS_WIDTH * (self.shelf.top + self.shelf.height).max(self.height()),
+            0,
+        );
+
+        for y in 0..height {
+            for x in 0..width {
+                // Clamping to the texture's edges fills in the border.
+                let texel = texture.texel(
+                    x.max(1).min(texture.width) - 1,
+                    y.max(1).min(texture.height) - 1,
+                );
+                let i = 4 * ((top + y) * ATLAS_WIDTH + left + x);
+                self.pixels[i..i + 4].copy_from_slice(&texel);
+            }
+        }
+
+        let origin = [left + 1, top + 1];
+        self.origins.insert(Rc::as_ptr(texture), origin);
+        self.textures.push(Rc::clone(texture));
+        self.changed = true;
+        origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(texture: Option<&Rc<Texture>>, texcoord: [f32; 2]) -> Vertex {
+        Vertex {
+            pos: nalgebra::Vector4::zeros(),
+            texcoord,
+            texture: texture.cloned(),
+            color: [1.; 4],
+            normal: nalgebra::Vector4::zeros(),
+        }
+    }
+
+    /// The atlas texel sampled by `vertex`.
+    fn sample(atlas: &mut Atlas, vertex: &Vertex) -> [u8; 4] {
+        let [u, v] = atlas.texcoord(vertex);
+        atlas.texel(u as usize, v as usize)
+    }
+
+    #[test]
+    fn textures_keep_their_texels() {
+        let texture = Rc::new(Texture::new(
+            2,
+            2,
+            vec![1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4],
+        ));
+        let mut atlas = Atlas::new();
+        assert_eq!(
+            sample(&mut atlas, &vertex(Some(&texture), [0.25, 0.25])),
+            [1; 4]
+        );
+        assert_eq!(
+            sample(&mut atlas, &vertex(Some(&texture), [0.75, 0.25])),
+            [2; 4]
+        );
+        assert_eq!(
+            sample(&mut atlas, &vertex(Some(&texture), [0.25, 0.75])),
+            [3; 4]
+        );
+        // Sampling at the far edges reads the border, and beyond them is clamped.
+        assert_eq!(
+            sample(&mut atlas, &vertex(Some(&texture), [1., 1.])),
+            [4; 4]
+        );
+        assert_eq!(
+            sample(&mut atlas, &vertex(Some(&texture), [-3., 9.])),
+            [3; 4]
+        );
+
+        let default = Texture::default();
+        assert_eq!(
+            sample(&mut atlas, &vertex(None, [0.5, 0.25])),
+            default.texel(32, 16)
+        );
+    }
+
+    #[test]
+    fn named_textures_are_shared() {
+        let foliage = Texture::named("foliage").unwrap();
+        assert_eq!((foliage.width(), foliage.height()), (64, 1));
+        assert_eq!(foliage.texel(0, 0), [255, 80, 255, 255]);
+        assert_eq!(foliage.texel(63, 0), [255, 206, 255, 255]);
+        assert!(Rc::ptr_eq(&foliage, &Texture::named("foliage").unwrap()));
+        assert_eq!(Texture::named("bark").unwrap().width(), 55);
+        assert!(Texture::named("wood").is_none());
+    }
+
+    #[test]
+    fn textures_do_not_overlap() {
+        let mut atlas = Atlas::new();
+        let textures: Vec<_> = (0..40u8)
+            .map(|i| {
+                Rc::new(Texture::new(
+                    100,
+                    10 + i as usize,
+                    vec![i; 4 * 100 * (10 + i as usize)],
+                ))
+            })
+            .collect();
+        let origins: Vec<_> = textures
+            .iter()
+            .map(|t| atlas.texcoord(&vertex(Some(t), [0., 0.])))
+            .collect();
+        assert!(atlas.take_changed());
+
+        for (i, texture) in textures.iter().enumerate() {
+            for &corner in &[[0., 0.], [1., 0.], [0., 1.], [1., 1.], [0.5, 0.5]] {
+                assert_eq!(
+                    sample(&mut atlas, &vertex(Some(texture), corner)),
+                    [i as u8; 4]
+                );
+            }
+        }
+        // Textures already in the atlas stay where they are.
+        assert!(!atlas.take_changed());
+        assert_eq!(
+            atlas.texcoord(&vertex(Some(&textures[0]), [0., 0.])),
+            origins[0]
+        );
+    }
+}
@@ -1,6 +1,10 @@
+use super::bvh::{self, Bvh};
+use super::packed::Packed;
 use super::program::Program;
-use super::Renderable;
+use super::texture::{Atlas, Texture};
+use super::Scene;
 use crate::utils::as_f32_array;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 
@@ -8,21 +12,28 @@ type GL = web_sys::WebGl2RenderingContext;
 
 const VERTEX_SHADER: &str = r#"#version 300 es
 
-in vec4 pos;
-in vec2 texcoord;
+layout(location = 0) in vec4 pos;
+layout(location = 1) in vec2 texcoord;
+layout(location = 2) in vec4 color;
+layout(location = 3) in vec4 normal;
 
 out vec4 vpos;
 out vec2 vtexcoord;
 out vec4 vdata;
+out vec4 vcolor;
+out vec4 vnormal;
 
 uniform mat4 four_camera_a;
 uniform vec4 four_camera_b;
 
 uniform mat4 three_camera;
+uniform vec2 atlas_size;
 
 void main() {
     vpos = pos;
-    vtexcoord = texcoord;
+    vtexcoord = texcoord / atlas_size;
+    vcolor = color;
+    vnormal = normal;
 
     vdata = four_camera_a * pos + four_camera_b;
 
@@ -31,44 +42,291 @@ void main() {
 
 "#;
 
+const FRAGMENT_SHADER: &str = r#"#version 300 es
+
+precision highp float;
+
+in vec4 vpos;
+in vec2 vtexcoord;
+in vec4 vdata;
+in vec4 vcolor;
+in vec4 vnormal;
+
+out vec4 color;
+
+uniform vec4 four_camera_pos;
+uniform vec4 four_camera_forward;
+uniform bool orthographic;
+uniform sampler2D tex;
+uniform vec3 three_screen_size;
+uniform vec4 light;
+
+uniform sampler2D regions;
+uniform sampler2D bvh;
+uniform int node_count;
+
+vec2 clip(vec2 minmax, vec4 pos, vec4 target, vec4 abcd, float e) {
+    float x = dot(abcd, pos) + e;
+    float y = dot(abcd, target) + e;
+
+    if (x > y) {
+        minmax.x = max(minmax.x, x/(x-y));
+    } else {
+        minmax.y = min(minmax.y, x/(x-y));
+    }
+
+    return minmax;
+}
+
+ivec2 texel(sampler2D tex, int i) {
+    int width = textureSize(tex, 0).x;
+    return ivec2(i % width, i / width);
+}
+
+bool intersects_region(vec4 pos, vec4 target, int first, int count) {
+    vec2 minmax = vec2(0., 0.999);
+
+    for (int i = first; i < first + count; i++) {
+        vec4 abcd = texelFetch(regions, texel(regions, 2 * i), 0);
+        float e = texelFetch(regions, texel(regions, 2 * i + 1), 0).x;
+        minmax = clip(minmax, pos, target, abcd, e);
+    }
+
+    return minmax.y > minmax.x;
+}
+
+bool intersects_box(vec4 pos, vec4 target, vec4 lo, vec4 hi) {
+    vec2 minmax = vec2(0., 0.999);
+
+    for (int i = 0; i < 4; i++) {
+        float d = target[i] - pos[i];
+        if (abs(d) < 1e-20) {
+            if (pos[i] < lo[i] || pos[i] > hi[i]) {
+                return false;
+            }
+        } else {
+            float a = (lo[i] - pos[i]) / d;
+            float b = (hi[i] - pos[i]) / d;
+            minmax.x = max(minmax.x, min(a, b));
+            minmax.y = min(minmax.y, max(a, b));
+        }
+    }
+
+    return minmax.y >= minmax.x;
+}
+
+bool intersects_scene(vec4 pos, vec4 target) {
+    int node = 0;
+
+    while (node < node_count) {
+        vec4 lo = texelFetch(bvh, texel(bvh, 3 * node), 0);
+        vec4 hi = texelFetch(bvh, texel(bvh, 3 * node + 1), 0);
+        vec4 info = texelFetch(bvh, texel(bvh, 3 * node + 2), 0);
+
+        if (intersects_box(pos, target, lo, hi)) {
+            if (info.z > 0. && intersects_region(pos, target, int(info.y), int(info.z))) {
+                return true;
+            }
+            node++;
+        } else {
+            node = int(info.x);
+        }
+    }
+
+    return false;
+}
+
+void main() {
+
+    vec3 data = vdata.xyz / vdata.w;
+
+    // The point from which this fragment is seen. In orthographic projection,
+    // lines of sight are parallel, starting from the hyperplane through the camera.
+    vec4 eye = four_camera_pos;
+    float depth = dot(vpos - four_camera_pos, four_camera_forward);
+    if (orthographic) {
+        eye = vpos - depth * four_camera_forward;
+    }
+
+    if (abs(data.x) > three_screen_size.x || abs(data.y) > three_screen_size.y || abs(data.z) > three_screen_size.z || abs(vdata.w) < 0.) {
+        // Outside three-screen, so invisible.
+        color = vec4(0.);
+    } else if (orthographic && depth < 0.) {
+        // Behind the camera, so invisible.
+        color = vec4(0.);
+    } else if (intersects_scene(eye, vpos)) {
+        // Occluded, so invisible.
+        color = vec4(0.);
+    } else {
+        // Diffuse lighting. Surfaces facing away from the light absorb up to twice as much as those facing it.
+        float shade = 1.0;
+        if (light != vec4(0.0) && vnormal != vec4(0.0)) {
+            shade = max(dot(normalize(vnormal), light), 0.0);
+        }
+        color = texture(tex, vtexcoord) * vec4(vcolor.rgb, 1.0) * vcolor.a * (2.0 - shade) / 5.0;
+    }
+}
+
+"#;
+
 pub struct Vertex {
     pub pos: nalgebra::Vector4<f32>,
     pub texcoord: [f32; 2],
+    /// The texture `texcoord` refers to, or `None` for the default texture.
+    pub texture: Option<Rc<Texture>>,
+    /// Multiplies the texture's color, except that the alpha channel scales all four channels,
+    /// so it says how opaque the vertex is.
+    pub color: [f32; 4],
+    /// The unit vector the surface faces, for lighting, or zero if the surface is not lit.
+    ///
+    /// A triangle in 4D has a whole plane of normals, so this is chosen relative to the cell containing it,
+    /// as in [`normal`](super::normal).
+    pub normal: nalgebra::Vector4<f32>,
+}
+
+const FLOATS_PER_VERTEX: usize = 14;
+
+/// Each half-space `h` of a region is stored as two texels, `(h[0], h[1], h[2], h[3])` and `(h[4], 0, 0, 0)`.
+pub(super) const FLOATS_PER_HALFSPACE: usize = 8;
+/// The width of the region and hierarchy textures, in texels.
+const TEXTURE_WIDTH: usize = 1536;
+
+/// A region of an object, given by its range of half-spaces within the object's data, and its bounding box.
+pub(super) struct Region {
+    pub offset: usize,
+    pub count: usize,
+    pub min: nalgebra::Vector4<f32>,
+    pub max: nalgebra::Vector4<f32>,
 }
 
-impl Vertex {
-    fn iter(&self) -> impl Iterator<Item = &f32> {
-        self.pos.iter().chain(self.texcoord.iter())
+pub(super) fn region_data(
+    regions: impl Iterator<Item = Vec<nalgebra::RowVector5<f32>>>,
+) -> (Vec<f32>, Vec<Region>) {
+    let mut data = Vec::new();
+    let mut out = Vec::new();
+    for region in regions {
+        // Empty regions can never occlude anything.
+        if let Some((min, max)) = crate::polytope::bounding_box(&region) {
+            out.push(Region {
+                offset: data.len() / FLOATS_PER_HALFSPACE,
+                count: region.len(),
+                min,
+                max,
+            });
+            for h in region {
+                data.extend_from_slice(&[h[0], h[1], h[2], h[3], h[4], 0., 0., 0.]);
+            }
+        }
     }
+    (data, out)
+}
+
+/// `n / d`, rounded up.
+///
+/// `usize::div_ceil` would need a newer compiler than the locked wasm-bindgen builds with.
+#[allow(clippy::manual_div_ceil)]
+fn div_ceil(n: usize, d: usize) -> usize {
+    (n + d - 1) / d
 }
 
-pub type RenderFunction = dyn Fn(Uniforms) -> Result<(), JsValue>;
+/// Upload `data` to a float texture `TEXTURE_WIDTH` texels wide, reallocating it if `rows` is too small.
+fn upload_rows(
+    gl: &GL,
+    texture: &web_sys::WebGlTexture,
+    rows: &mut usize,
+    data: &[f32],
+    first_row: usize,
+) -> Result<(), JsValue> {
+    let floats_per_row = TEXTURE_WIDTH * 4;
+    let row_count = div_ceil(data.len(), floats_per_row);
+    let mut data = data.to_vec();
+    data.resize(row_count * floats_per_row, 0.);
+
+    gl.bind_texture(GL::TEXTURE_2D, Some(texture));
+    if first_row + row_count > *rows {
+        assert_eq!(first_row, 0, "only whole-texture uploads may reallocate");
+        *rows = row_count.max(2 * *rows).max(1);
+        data.resize(*rows * floats_per_row, 0.);
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
+            GL::TEXTURE_2D,
+            0,                    // level
+            GL::RGBA32F as i32,   // internal_format
+            TEXTURE_WIDTH as i32, // width
+            *rows as i32,         // height
+            0,                    // border
+            GL::RGBA,             // format
+            GL::FLOAT,            // type
+            Some(&as_f32_array(&data)?.into()),
+        )?;
+    } else if row_count > 0 {
+        gl.tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_array_buffer_view(
+            GL::TEXTURE_2D,
+            0,                    // level
+            0,                    // xoffset
+            first_row as i32,     // yoffset
+            TEXTURE_WIDTH as i32, // width
+            row_count as i32,     // height
+            GL::RGBA,             // format
+            GL::FLOAT,            // type
+            Some(&as_f32_array(&data)?.into()),
+        )?;
+    }
+    Ok(())
+}
+
+fn float_texture(gl: &GL) -> Result<web_sys::WebGlTexture, JsValue> {
+    let texture = gl.create_texture().ok_or("create_texture failed")?;
+    gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
+    Ok(texture)
+}
+
+pub type RenderFunction = dyn FnMut(&mut Scene, Uniforms) -> Result<(), JsValue>;
+/// Draw the scene into the intermediate texture, which is the given size in pixels.
+pub(super) type DrawFunction = dyn FnMut(&mut Scene, Uniforms, [i32; 2]) -> Result<(), JsValue>;
 
 pub struct Uniforms {
     pub four_camera: nalgebra::Matrix4x5<f32>,
     pub four_camera_pos: nalgebra::Vector4<f32>,
+    /// The direction the four-camera is looking in, as a unit vector.
+    pub four_camera_forward: nalgebra::Vector4<f32>,
+    /// Whether the four-camera's lines of sight are parallel, rather than meeting at `four_camera_pos`.
+    pub orthographic: bool,
     pub three_cameras: [nalgebra::Matrix4<f32>; 2],
     pub three_screen_size: [f32; 3],
+    /// The unit vector pointing towards the light, or zero to turn lighting off.
+    pub light: nalgebra::Vector4<f32>,
 }
 
 pub fn make_fn(
     gl: Rc<GL>,
     render_texture: &web_sys::WebGlTexture,
-    renderable: impl Renderable,
-) -> Result<Box<RenderFunction>, JsValue> {
-    let vertices: Vec<Vertex> = renderable.triangles().collect();
-    let data: Vec<f32> = vertices.iter().flat_map(|v| v.iter()).copied().collect();
-
-    let program = Program::new(Rc::clone(&gl), VERTEX_SHADER, &renderable.fragment_shader())?;
+) -> Result<Box<DrawFunction>, JsValue> {
+    let program = Program::new(Rc::clone(&gl), VERTEX_SHADER, FRAGMENT_SHADER)?;
 
-    let pos_loc = program.attribute("pos")?;
-    let texcoord_loc = program.attribute("texcoord")?;
     let four_camera_a_loc = program.uniform("four_camera_a")?;
     let four_camera_b_loc = program.uniform("four_camera_b")?;
     let three_camera_loc = program.uniform("three_camera")?;
     let four_camera_pos_loc = program.uniform("four_camera_pos")?;
+    let four_camera_forward_loc = program.uniform("four_camera_forward")?;
+    let orthographic_loc = program.uniform("orthographic")?;
     let three_screen_size_loc = program.uniform("three_screen_size")?;
+    let light_loc = program.uniform("light")?;
     let texture_loc = program.uniform("tex")?;
+    let atlas_size_loc = program.uniform("atlas_size")?;
+    let regions_loc = program.uniform("regions")?;
+    let bvh_loc = program.uniform("bvh")?;
+    let node_count_loc = program.uniform("node_count")?;
+
+    let mut vertices = Packed::new(vec![0.; FLOATS_PER_VERTEX]);
+    let mut halfspaces = Packed::new(vec![0.; FLOATS_PER_HALFSPACE]);
+    let mut regions: BTreeMap<usize, Vec<Region>> = BTreeMap::new();
+    let mut hierarchy = Bvh::new(Vec::new());
+    // The number of vertices, and texture rows, for which space is allocated on the GPU.
+    let mut vertex_capacity = 0;
+    let mut region_rows = 0;
+    let mut bvh_rows = 0;
 
     let vao = gl
         .create_vertex_array()
@@ -77,15 +335,15 @@ pub fn make_fn(
 
     let vertex_buffer = gl.create_buffer().ok_or("create_buffer failed")?;
     gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vertex_buffer));
-    gl.enable_vertex_attrib_array(pos_loc);
-    gl.vertex_attrib_pointer_with_i32(pos_loc, 4, GL::FLOAT, false, 6 * 4, 0);
-    gl.enable_vertex_attrib_array(texcoord_loc);
-    gl.vertex_attrib_pointer_with_i32(texcoord_loc, 2, GL::FLOAT, false, 6 * 4, 4 * 4);
-    gl.buffer_data_with_array_buffer_view(
-        GL::ARRAY_BUFFER,
-        &as_f32_array(&data)?.into(),
-        GL::STATIC_DRAW,
-    );
+    gl.enable_vertex_attrib_array(0);
+    let stride = (FLOATS_PER_VERTEX * 4) as i32;
+    gl.vertex_attrib_pointer_with_i32(0, 4, GL::FLOAT, false, stride, 0);
+    gl.enable_vertex_attrib_array(1);
+    gl.vertex_attrib_pointer_with_i32(1, 2, GL::FLOAT, false, stride, 4 * 4);
+    gl.enable_vertex_attrib_array(2);
+    gl.vertex_attrib_pointer_with_i32(2, 4, GL::FLOAT, false, stride, 6 * 4);
+    gl.enable_vertex_attrib_array(3);
+    gl.vertex_attrib_pointer_with_i32(3, 4, GL::FLOAT, false, stride, 10 * 4);
 
     let framebuffer = gl.create_framebuffer().ok_or("create_framebuffer failed")?;
     gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&framebuffer));
@@ -97,26 +355,117 @@ pub fn make_fn(
         0,
     );
 
+    let mut atlas = Atlas::new();
     let texture = gl.create_texture().ok_or("create_texture failed")?;
     gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
-
-    gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
-        GL::TEXTURE_2D,
-        0,                 // level
-        GL::RGBA as i32,   // internal_format
-        64,                // width
-        64,                // height
-        0,                 // border
-        GL::RGBA,          // format
-        GL::UNSIGNED_BYTE, // type
-        Some(include_bytes!("../../resources/texture")),
-    )?;
     gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
     gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
     gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
     gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
 
-    let render: Box<RenderFunction> = Box::new(move |uniforms| {
+    let region_texture = float_texture(&gl)?;
+    let bvh_texture = float_texture(&gl)?;
+
+    let render: Box<DrawFunction> = Box::new(move |scene, uniforms, size| {
+        let dirty = scene.take_dirty();
+        for &index in &dirty {
+            let mut data = Vec::new();
+            for v in scene.triangles_of(index) {
+                data.extend(v.pos.iter());
+                data.extend(&atlas.texcoord(&v));
+                data.extend(&v.color);
+                data.extend(v.normal.iter());
+            }
+            vertices.set(index, &data);
+
+            let (data, object_regions) = region_data(scene.regions_of(index).into_iter());
+            halfspaces.set(index, &data);
+            regions.insert(index, object_regions);
+        }
+        if !dirty.is_empty() {
+            hierarchy = Bvh::new(
+                regions
+                    .iter()
+                    .flat_map(|(&index, object_regions)| {
+                        let start = halfspaces.range(index).map_or(0, |r| r.start);
+                        object_regions.iter().map(move |r| bvh::Leaf {
+                            first: start + r.offset,
+                            count: r.count,
+                            min: r.min,
+                            max: r.max,
+                        })
+                    })
+                    .collect(),
+            );
+            upload_rows(&gl, &bvh_texture, &mut bvh_rows, &hierarchy.data(), 0)?;
+        }
+
+        if atlas.take_changed() {
+            gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
+            gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                GL::TEXTURE_2D,
+                0,                     // level
+                GL::RGBA as i32,       // internal_format
+                atlas.width() as i32,  // width
+                atlas.height() as i32, // height
+                0,                     // border
+                GL::RGBA,              // format
+                GL::UNSIGNED_BYTE,     // type
+                Some(atlas.pixels()),
+            )?;
+        }
+
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vertex_buffer));
+        if vertices.len() > vertex_capacity {
+            vertex_capacity = vertices.len().max(2 * vertex_capacity);
+            gl.buffer_data_with_i32(
+                GL::ARRAY_BUFFER,
+                (vertex_capacity * FLOATS_PER_VERTEX * 4) as i32,
+                GL::DYNAMIC_DRAW,
+            );
+            gl.buffer_sub_data_with_i32_and_array_buffer_view(
+                GL::ARRAY_BUFFER,
+                0,
+                &as_f32_array(vertices.data(0..vertices.len()))?.into(),
+            );
+            vertices.take_changed();
+        } else {
+            for range in vertices.take_changed() {
+                gl.buffer_sub_data_with_i32_and_array_buffer_view(
+                    GL::ARRAY_BUFFER,
+                    (range.start * FLOATS_PER_VERTEX * 4) as i32,
+                    &as_f32_array(vertices.data(range))?.into(),
+                );
+            }
+        }
+
+        // The region texture is updated a whole row at a time.
+        let halfspaces_per_row = TEXTURE_WIDTH / 2;
+        let changed = halfspaces.take_changed();
+        if halfspaces.len() > region_rows * halfspaces_per_row {
+            upload_rows(
+                &gl,
+                &region_texture,
+                &mut region_rows,
+                halfspaces.data(0..halfspaces.len()),
+                0,
+            )?;
+        } else {
+            for range in changed {
+                let first = range.start / halfspaces_per_row;
+                let last = div_ceil(range.end, halfspaces_per_row);
+                upload_rows(
+                    &gl,
+                    &region_texture,
+                    &mut region_rows,
+                    halfspaces.data(
+                        first * halfspaces_per_row..halfspaces.len().min(last * halfspaces_per_row),
+                    ),
+                    first,
+                )?;
+            }
+        }
+
         gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&framebuffer));
         gl.bind_vertex_array(Some(&vao));
 
@@ -153,6 +502,15 @@ pub fn make_fn(
             uniforms.four_camera_pos[3],
         );
 
+        gl.uniform4f(
+            Some(&four_camera_forward_loc),
+            uniforms.four_camera_forward[0],
+            uniforms.four_camera_forward[1],
+            uniforms.four_camera_forward[2],
+            uniforms.four_camera_forward[3],
+        );
+        gl.uniform1i(Some(&orthographic_loc), uniforms.orthographic as i32);
+
         gl.uniform3f(
             Some(&three_screen_size_loc),
             uniforms.three_screen_size[0],
@@ -160,30 +518,42 @@ pub fn make_fn(
             uniforms.three_screen_size[2],
         );
 
+        gl.uniform4f(
+            Some(&light_loc),
+            uniforms.light[0],
+            uniforms.light[1],
+            uniforms.light[2],
+            uniforms.light[3],
+        );
+
+        gl.active_texture(GL::TEXTURE0);
         gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
         gl.uniform1i(Some(&texture_loc), 0);
-
-        gl.viewport(0, 0, 800, 800);
-        gl.uniform_matrix4fv_with_f32_array(
-            Some(&three_camera_loc),
-            false,
-            &uniforms.three_cameras[0]
-                .into_iter()
-                .copied()
-                .collect::<Vec<_>>(),
+        gl.uniform2f(
+            Some(&atlas_size_loc),
+            atlas.width() as f32,
+            atlas.height() as f32,
         );
-        gl.draw_arrays(GL::TRIANGLES, 0, (data.len() / 6) as i32);
 
-        gl.viewport(800, 0, 800, 800);
-        gl.uniform_matrix4fv_with_f32_array(
-            Some(&three_camera_loc),
-            false,
-            &uniforms.three_cameras[1]
-                .into_iter()
-                .copied()
-                .collect::<Vec<_>>(),
-        );
-        gl.draw_arrays(GL::TRIANGLES, 0, (data.len() / 6) as i32);
+        gl.active_texture(GL::TEXTURE1);
+        gl.bind_texture(GL::TEXTURE_2D, Some(&region_texture));
+        gl.uniform1i(Some(&regions_loc), 1);
+
+        gl.active_texture(GL::TEXTURE2);
+        gl.bind_texture(GL::TEXTURE_2D, Some(&bvh_texture));
+        gl.uniform1i(Some(&bvh_loc), 2);
+        gl.uniform1i(Some(&node_count_loc), hierarchy.len() as i32);
+        gl.active_texture(GL::TEXTURE0);
+
+        for (viewport, three_camera) in super::viewports(size).iter().zip(&uniforms.three_cameras) {
+            gl.viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+            gl.uniform_matrix4fv_with_f32_array(
+                Some(&three_camera_loc),
+                false,
+                &three_camera.into_iter().copied().collect::<Vec<_>>(),
+            );
+            gl.draw_arrays(GL::TRIANGLES, 0, vertices.len() as i32);
+        }
 
         Ok(())
     });
@@ -30,3 +30,32 @@ pub fn event_listener<Msg: 'static>(
     closure.forget();
     Ok(())
 }
+
+/// Call `f` once `promise` settles, with the value it resolved to or the reason it was rejected.
+pub fn on_settled(promise: &js_sys::Promise, f: impl FnOnce(Result<JsValue, JsValue>) + 'static) {
+    let f = std::rc::Rc::new(std::cell::RefCell::new(Some(f)));
+    let g = f.clone();
+    let resolve = Closure::once(move |value| {
+        if let Some(f) = f.borrow_mut().take() {
+            f(Ok(value));
+        }
+    });
+    let reject = Closure::once(move |reason| {
+        if let Some(g) = g.borrow_mut().take() {
+            g(Err(reason));
+        }
+    });
+    let _ = promise.then2(&resolve, &reject);
+    resolve.forget();
+    reject.forget();
+}
+
+/// A readable description of a JavaScript error.
+pub fn describe(err: &JsValue) -> String {
+    err.as_string()
+        .or_else(|| {
+            err.dyn_ref::<js_sys::Error>()
+                .map(|err| String::from(err.message()))
+        })
+        .unwrap_or_else(|| format!("{:?}", err))
+}